@@ -0,0 +1,201 @@
+use super::{create_data_dir, data_dir, Error, Network, Result};
+use helium_wallet::keypair::PublicKey;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf, str::FromStr, sync::Mutex};
+
+const ADDRESS_BOOK_FILE: &str = "addressbook.json";
+const LABEL_PREFIX: char = '@';
+
+/// The address book as loaded by `labeled` and `Address::from_str`, read
+/// once per session
+static LOADED: Mutex<Option<AddressBook>> = Mutex::new(None);
+
+/// Labeled addresses stored in the local data directory. Entries are stored
+/// as b58 strings keyed by label.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    entries: BTreeMap<String, PublicKey>,
+}
+
+impl AddressBook {
+    pub fn path() -> Result<PathBuf> {
+        Ok(data_dir()?.join(ADDRESS_BOOK_FILE))
+    }
+
+    /// Loads the address book, returning an empty one if none has been
+    /// created yet
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = fs::File::open(path)?;
+        let raw: BTreeMap<String, String> = serde_json::from_reader(file)?;
+        let mut entries = BTreeMap::new();
+        for (label, address) in raw {
+            let key = PublicKey::from_str(&address).map_err(|_| {
+                Error::AddressBook(format!("entry @{} has invalid address {}", label, address))
+            })?;
+            entries.insert(label, key);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn save(&self) -> Result {
+        let raw: BTreeMap<&String, String> = self
+            .entries
+            .iter()
+            .map(|(label, key)| (label, key.to_string()))
+            .collect();
+        create_data_dir()?;
+        let file = fs::File::create(Self::path()?)?;
+        serde_json::to_writer_pretty(file, &raw)?;
+        *LOADED.lock().unwrap_or_else(|err| err.into_inner()) = None;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, label: &str, key: PublicKey, force: bool) -> Result {
+        validate_label(label)?;
+        if !force && self.entries.contains_key(label) {
+            return Err(Error::AddressBook(format!(
+                "label @{} already exists. Use --force to replace it",
+                label
+            )));
+        }
+        self.entries.insert(label.to_string(), key);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, label: &str) -> Result<PublicKey> {
+        self.entries
+            .remove(label)
+            .ok_or_else(|| Error::AddressBook(format!("unknown label @{}", label)))
+    }
+
+    pub fn get(&self, label: &str) -> Option<&PublicKey> {
+        self.entries.get(label)
+    }
+
+    /// Finds the label, if any, for the given address
+    pub fn label_for(&self, key: &PublicKey) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| *entry == key)
+            .map(|(label, _)| label.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PublicKey)> {
+        self.entries.iter()
+    }
+}
+
+/// Formats an address for display, appending its address book label if it
+/// has one. A missing or unreadable address book simply yields the address.
+pub fn labeled(key: &PublicKey) -> String {
+    let mut loaded = LOADED.lock().unwrap_or_else(|err| err.into_inner());
+    let book = loaded.get_or_insert_with(|| AddressBook::load().unwrap_or_default());
+    match book.label_for(key) {
+        Some(label) => format!("{} ({}{})", key, LABEL_PREFIX, label),
+        None => key.to_string(),
+    }
+}
+
+fn validate_label(label: &str) -> Result {
+    if label.is_empty()
+        || !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(Error::AddressBook(format!(
+            "invalid label \"{}\". Use letters, digits, '-', '_' or '.'",
+            label
+        )));
+    }
+    Ok(())
+}
+
+/// An address given on the command line or in an input file. It is either a
+/// b58 encoded public key or an `@label` from the address book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    key: PublicKey,
+    label: Option<String>,
+}
+
+impl Address {
+    /// Returns the public key for this address, refusing labels whose key
+    /// is on a different network than the one the Ledger is using
+    pub fn resolve(&self, network: Network) -> Result<PublicKey> {
+        if let Some(label) = &self.label {
+            if self.key.network != network {
+                return Err(Error::AddressBook(format!(
+                    "@{} is a {} address but the Ledger is on {}",
+                    label, self.key.network, network
+                )));
+            }
+        }
+        Ok(self.key.clone())
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(label) = s.strip_prefix(LABEL_PREFIX) {
+            // unlike `labeled`, an unreadable address book is an error here
+            let mut loaded = LOADED.lock().unwrap_or_else(|err| err.into_inner());
+            if loaded.is_none() {
+                *loaded = Some(AddressBook::load()?);
+            }
+            let key = loaded
+                .as_ref()
+                .and_then(|book| book.get(label))
+                .ok_or_else(|| Error::AddressBook(format!("unknown label @{}", label)))?;
+            Ok(Address {
+                key: key.clone(),
+                label: Some(label.to_string()),
+            })
+        } else {
+            Ok(Address {
+                key: PublicKey::from_str(s)?,
+                label: None,
+            })
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{}{}", LABEL_PREFIX, label),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct AddressVisitor;
+
+        impl<'de> Visitor<'de> for AddressVisitor {
+            type Value = Address;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("b58 address or @label")
+            }
+
+            fn visit_str<E>(self, value: &str) -> std::result::Result<Address, E>
+            where
+                E: de::Error,
+            {
+                Address::from_str(value).map_err(|e| de::Error::custom(e.to_string()))
+            }
+        }
+
+        deserializer.deserialize_str(AddressVisitor)
+    }
+}
@@ -0,0 +1,330 @@
+use super::{Error, Network, Result, DEFAULT_TESTNET_BASE_URL, USER_AGENT};
+use helium_api::{
+    models::{Account, Validator},
+    Client,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{env, future::Future, sync::Mutex, time::Duration};
+
+/// Seconds before an API request times out, unless set with
+/// HELIUM_API_TIMEOUT
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Attempts per endpoint for requests failing with a transient error
+const ATTEMPTS: u32 = 3;
+
+/// The endpoint in use per network, as an index into its `urls`. Set by the
+/// health check and moved on failover.
+static ACTIVE: Mutex<Vec<(String, usize)>> = Mutex::new(Vec::new());
+
+/// API base URLs of a network, in order of preference. HELIUM_API_URL and
+/// HELIUM_TESTNET_API_URL take a comma separated list.
+pub fn urls(network: Network) -> Vec<String> {
+    let (var, default) = match network {
+        Network::MainNet => ("HELIUM_API_URL", helium_api::DEFAULT_BASE_URL),
+        Network::TestNet => ("HELIUM_TESTNET_API_URL", DEFAULT_TESTNET_BASE_URL),
+    };
+    let urls: Vec<String> = env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(|url| url.trim().trim_end_matches('/'))
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect();
+    if urls.is_empty() {
+        vec![default.to_string()]
+    } else {
+        urls
+    }
+}
+
+/// The request timeout, configurable in seconds with HELIUM_API_TIMEOUT
+pub fn timeout() -> Duration {
+    Duration::from_secs(
+        env::var("HELIUM_API_TIMEOUT")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS),
+    )
+}
+
+fn active(network: Network) -> Option<usize> {
+    ACTIVE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .find(|(active, _)| *active == network.to_string())
+        .map(|(_, index)| *index)
+}
+
+fn set_active(network: Network, index: usize) {
+    let mut active = ACTIVE.lock().unwrap_or_else(|err| err.into_inner());
+    active.retain(|(active, _)| *active != network.to_string());
+    active.push((network.to_string(), index));
+}
+
+/// The endpoint currently in use for the given network
+pub fn url(network: Network) -> String {
+    let urls = urls(network);
+    urls[active(network).unwrap_or(0) % urls.len()].clone()
+}
+
+/// A `helium_api` client for the given endpoint with the configured timeout
+pub fn client(url: &str) -> Client {
+    Client::new_with_timeout(url.to_string(), USER_AGENT, timeout().as_secs())
+}
+
+/// Picks the first endpoint of the network that answers a health check,
+/// once per session on its first request. A single endpoint is used without
+/// a check.
+pub async fn select(network: Network) -> Result<usize> {
+    if let Some(index) = active(network) {
+        return Ok(index);
+    }
+    let urls = urls(network);
+    if urls.len() > 1 {
+        let client = http_client()?;
+        for (index, url) in urls.iter().enumerate() {
+            let response = client.get(format!("{}/blocks/height", url)).send().await;
+            if matches!(response, Ok(response) if response.status().is_success()) {
+                set_active(network, index);
+                return Ok(index);
+            }
+            eprintln!("WARNING: API at {} failed its health check", url);
+        }
+        return Err(Error::Api(format!(
+            "no {} API endpoint is healthy",
+            network
+        )));
+    }
+    set_active(network, 0);
+    Ok(0)
+}
+
+/// Runs a request against the endpoints of a network, passing it the base
+/// URL to use. Transient failures (timeouts, connection errors, 429 and 5xx
+/// responses) are retried with exponential backoff before the request fails
+/// over to the next endpoint, so only requests that are safe to repeat may
+/// be run this way.
+pub async fn with_failover<T, F, Fut>(network: Network, request: F) -> Result<T>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let urls = urls(network);
+    let first = select(network).await?;
+    let mut last_err = None;
+    for offset in 0..urls.len() {
+        let index = (first + offset) % urls.len();
+        let url = &urls[index];
+        if offset > 0 {
+            eprintln!("WARNING: failing over to API at {}", url);
+        }
+        let mut delay = Duration::from_secs(1);
+        for attempt in 1..=ATTEMPTS {
+            match request(url.clone()).await {
+                Ok(value) => {
+                    set_active(network, index);
+                    return Ok(value);
+                }
+                Err(err) if err.is_transient() => {
+                    if attempt < ATTEMPTS {
+                        eprintln!(
+                            "WARNING: request to {} failed ({}). Retrying in {}s",
+                            url,
+                            err,
+                            delay.as_secs()
+                        );
+                        tokio::time::sleep(delay).await;
+                        delay *= 2;
+                    }
+                    last_err = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| Error::Api(format!("no {} API endpoint", network))))
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .gzip(true)
+        .timeout(timeout())
+        .build()?)
+}
+
+/// Sends a request, returning None if the API does not know the requested
+/// resource
+async fn fetch(request: reqwest::RequestBuilder) -> Result<Option<Value>> {
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(response.error_for_status()?.json().await?))
+}
+
+/// Looks up an account, failing over to other endpoints if needed
+pub async fn account(network: Network, address: &str) -> Result<Account> {
+    with_failover(network, |url| async move {
+        helium_api::accounts::get(&client(&url), address)
+            .await
+            .map_err(Error::from)
+    })
+    .await
+}
+
+/// Looks up a validator, failing over to other endpoints if needed
+pub async fn validator(network: Network, address: &str) -> Result<Validator> {
+    with_failover(network, |url| async move {
+        helium_api::validators::get(&client(&url), address)
+            .await
+            .map_err(Error::from)
+    })
+    .await
+}
+
+/// Fetches the `data` of an API endpoint not covered by `helium_api`.
+/// Returns None if the API does not know the requested resource.
+pub async fn get<T: DeserializeOwned>(
+    network: Network,
+    path: &str,
+    query: &[(&str, &str)],
+) -> Result<Option<T>> {
+    let client = http_client()?;
+    let body = with_failover(network, |url| {
+        fetch(client.get(format!("{}{}", url, path)).query(query))
+    })
+    .await?;
+    match body.and_then(|mut body| body.get_mut("data").map(Value::take)) {
+        Some(Value::Null) | None => Ok(None),
+        Some(data) => Ok(Some(serde_json::from_value(data)?)),
+    }
+}
+
+/// Fetches every page of a cursor based API listing, such as account
+/// activity, and returns the concatenated `data` of all pages
+pub async fn get_all(network: Network, path: &str, query: &[(&str, &str)]) -> Result<Vec<Value>> {
+    let client = http_client()?;
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut page_query = query.to_vec();
+        if let Some(cursor) = &cursor {
+            page_query = vec![("cursor", cursor.as_str())];
+        }
+        let body = with_failover(network, |url| {
+            fetch(client.get(format!("{}{}", url, path)).query(&page_query))
+        })
+        .await?
+        .ok_or_else(|| Error::Api(format!("{} not found", path)))?;
+        if let Some(Value::Array(data)) = body.get("data") {
+            items.extend(data.iter().cloned());
+        }
+        match body.get("cursor").and_then(Value::as_str) {
+            Some(next) => cursor = Some(next.to_string()),
+            None => return Ok(items),
+        }
+    }
+}
+
+/// The current height of the chain
+pub async fn height(network: Network) -> Result<u64> {
+    let height: Option<Value> = get(network, "/blocks/height", &[]).await?;
+    height
+        .as_ref()
+        .and_then(|height| height.get("height"))
+        .and_then(Value::as_u64)
+        .ok_or_else(|| Error::Api("missing block height".to_string()))
+}
+
+/// A transaction submitted to the API that has not cleared yet, or that
+/// failed
+#[derive(Debug, Clone)]
+pub struct PendingTxn {
+    pub hash: String,
+    pub txn_type: String,
+    pub status: String,
+    pub nonce: Option<u64>,
+    pub created_at: String,
+    pub failed_reason: String,
+}
+
+impl PendingTxn {
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+}
+
+/// Lists the pending transactions the API holds for the given account
+pub async fn pending_txns(network: Network, address: &str) -> Result<Vec<PendingTxn>> {
+    let str_field = |value: &Value, field: &str| {
+        value
+            .get(field)
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok(get_all(
+        network,
+        &format!("/accounts/{}/pending_transactions", address),
+        &[],
+    )
+    .await?
+    .iter()
+    .map(|pending| PendingTxn {
+        hash: str_field(pending, "hash"),
+        txn_type: str_field(pending, "type"),
+        status: str_field(pending, "status"),
+        nonce: pending
+            .get("txn")
+            .and_then(|txn| txn.get("nonce"))
+            .and_then(Value::as_u64),
+        created_at: str_field(pending, "created_at"),
+        failed_reason: str_field(pending, "failed_reason"),
+    })
+    .collect())
+}
+
+/// Where a transaction is in its life on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxnStatus {
+    /// Included in a block at the given height
+    Confirmed(u64),
+    /// Known to the API but not yet in a block
+    Pending,
+    /// Rejected by the chain for the given reason
+    Failed(String),
+    /// Never seen by the API
+    Unknown,
+}
+
+/// Looks up a transaction hash in the chain and in the API's pending
+/// transactions
+pub async fn txn_status(network: Network, hash: &str) -> Result<TxnStatus> {
+    if let Some(txn) = get::<Value>(network, &format!("/transactions/{}", hash), &[]).await? {
+        let height = txn.get("height").and_then(Value::as_u64).unwrap_or(0);
+        return Ok(TxnStatus::Confirmed(height));
+    }
+    let pending: Option<Value> =
+        get(network, &format!("/pending_transactions/{}", hash), &[]).await?;
+    // the API reports a list of submissions for a pending hash
+    let latest = match pending {
+        Some(Value::Array(mut submissions)) => submissions.pop(),
+        other => other,
+    };
+    Ok(match latest {
+        Some(status) => match status.get("status").and_then(Value::as_str) {
+            Some("failed") => TxnStatus::Failed(
+                status
+                    .get("failed_reason")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string(),
+            ),
+            _ => TxnStatus::Pending,
+        },
+        None => TxnStatus::Unknown,
+    })
+}
@@ -0,0 +1,63 @@
+use super::*;
+use crate::addressbook::AddressBook;
+
+#[derive(Debug, StructOpt)]
+/// Manage the local address book. Labels can be used as `@label` anywhere an
+/// address is expected.
+pub enum Cmd {
+    /// Add a labeled address
+    Add(Add),
+    /// List all labeled addresses
+    List,
+    /// Remove a labeled address
+    Remove(Remove),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Add {
+    /// Label to refer to the address by (letters, digits, '-', '_' or '.')
+    label: String,
+    /// Address to label
+    address: PublicKey,
+    /// Replace the address if the label already exists
+    #[structopt(long)]
+    force: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Remove {
+    /// Label to remove
+    label: String,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let mut book = AddressBook::load()?;
+        match self {
+            Cmd::Add(add) => {
+                let label = add.label.trim_start_matches('@');
+                book.insert(label, add.address.clone(), add.force)?;
+                book.save()?;
+                println!("Added @{} for {}", label, add.address);
+            }
+            Cmd::List => print_address_book(&book),
+            Cmd::Remove(remove) => {
+                let label = remove.label.trim_start_matches('@');
+                let key = book.remove(label)?;
+                book.save()?;
+                println!("Removed @{} for {}", label, key);
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn print_address_book(book: &AddressBook) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row!["Label", "Address", "Network"]);
+    for (label, key) in book.iter() {
+        table.add_row(row![format!("@{}", label), key, key.network]);
+    }
+    table.printstd();
+}
@@ -1,15 +1,27 @@
 use super::*;
+use crate::api;
+use futures::stream::{self, StreamExt};
 use helium_api::models::Account;
+use rust_decimal::Decimal;
+use std::num::NonZeroU8;
 
 #[derive(Debug, StructOpt)]
 pub struct Cmd {
     /// Display QR code for a given single wallet.
     #[structopt(long = "qr")]
     pub qr_code: bool,
-    /// Scans all accounts up until selected account index
-    /// This is useful for displaying all balances
+    /// Discovers all accounts of this Ledger by deriving accounts upwards
+    /// from index 0 until --gap-limit consecutive accounts have no activity
+    /// on chain. This is useful for displaying all balances
     #[structopt(long = "scan")]
     pub scan: bool,
+    /// Number of consecutive accounts without activity after which a scan
+    /// stops. Must be at least 1
+    #[structopt(long = "gap-limit", default_value = "3")]
+    pub gap_limit: NonZeroU8,
+    /// Maximum number of concurrent API lookups during a scan
+    #[structopt(long = "parallel", default_value = "4")]
+    pub parallel: u8,
 }
 
 impl Cmd {
@@ -22,23 +34,22 @@ impl Cmd {
             if self.qr_code {
                 println!("WARNING: to output a QR Code, do not use scan")
             }
-            let mut account_results = Vec::new();
             let network = version.network;
-            for i in 0..opts.account {
-                let pubkey = get_pubkey(i, &ledger_transport, PubkeyDisplay::Off).await?;
-                let client = new_client(pubkey.network);
-                let address = pubkey.to_string();
-                let result = accounts::get(&client, &address).await;
-                account_results.push((pubkey, result));
-            }
-            print_balance(network, &account_results).await?;
+            let account_results =
+                discover(&ledger_transport, network, self.gap_limit, self.parallel).await?;
+            let price = Price::for_opts(&opts, network).await?;
+            print_balance(network, &account_results, price.as_ref()).await?;
         } else {
             let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
             let pubkey_str = pubkey.to_string();
-            let client = new_client(pubkey.network);
-            let address = pubkey.to_string();
-            let result = accounts::get(&client, &address).await;
-            print_balance(pubkey.network, &vec![(pubkey, result)]).await?;
+            let result = api::account(pubkey.network, &pubkey.to_string()).await;
+            let price = Price::for_opts(&opts, pubkey.network).await?;
+            print_balance(
+                pubkey.network,
+                &vec![(opts.account, pubkey, result)],
+                price.as_ref(),
+            )
+            .await?;
             if self.qr_code {
                 print_qr(&pubkey_str)?;
             }
@@ -49,12 +60,97 @@ impl Cmd {
     }
 }
 
+/// Derives accounts from index 0 upwards, looking them up in batches of
+/// `parallel` concurrent API requests, until `gap_limit` consecutive accounts
+/// have no on-chain activity. Trailing accounts without activity or unknown
+/// to the API are dropped except for account 0. A failed lookup aborts the
+/// scan.
+#[allow(clippy::borrowed_box)]
+pub async fn discover(
+    ledger_transport: &Box<dyn LedgerTransport>,
+    network: Network,
+    gap_limit: NonZeroU8,
+    parallel: u8,
+) -> Result<ResultsVec> {
+    let gap_limit = gap_limit.get();
+    let parallel = parallel.max(1) as usize;
+    let mut results = ResultsVec::new();
+    let mut gap = 0;
+    let mut next: usize = 0;
+
+    while gap < gap_limit && next <= u8::MAX as usize {
+        let batch_end = (next + parallel).min(u8::MAX as usize + 1);
+        // the Ledger can only derive one key at a time
+        let mut pubkeys = Vec::with_capacity(batch_end - next);
+        for index in next..batch_end {
+            let index = index as u8;
+            pubkeys.push((
+                index,
+                get_pubkey(index, ledger_transport, PubkeyDisplay::Off).await?,
+            ));
+        }
+        next = batch_end;
+
+        let fetched: ResultsVec = stream::iter(pubkeys)
+            .map(|(index, pubkey)| async move {
+                let result = api::account(network, &pubkey.to_string()).await;
+                (index, pubkey, result)
+            })
+            .buffered(parallel)
+            .collect()
+            .await;
+
+        for (index, pubkey, result) in fetched {
+            if gap >= gap_limit {
+                break;
+            }
+            // an account the API does not know has no activity, but any other
+            // failure leaves the gap unknown so the scan cannot go on
+            let result = match result {
+                Err(err) if !err.is_not_found() => {
+                    println!("Scan aborted looking up account {}", index);
+                    return Err(err);
+                }
+                result => result,
+            };
+            let active = result.as_ref().map(has_activity).unwrap_or(false);
+            gap = if active { 0 } else { gap + 1 };
+            results.push((index, pubkey, result));
+        }
+    }
+
+    while results.len() > 1
+        && results
+            .last()
+            .map(|(_, _, result)| !matches!(result, Ok(account) if has_activity(account)))
+            .unwrap_or(false)
+    {
+        results.pop();
+    }
+    Ok(results)
+}
+
+fn has_activity(account: &Account) -> bool {
+    account.speculative_nonce > 0
+        || !account.balance.get_decimal().is_zero()
+        || !account.staked_balance.get_decimal().is_zero()
+        || account.dc_balance > 0
+        || !account.sec_balance.get_decimal().is_zero()
+        || !account.iot_balance.get_decimal().is_zero()
+        || !account.mobile_balance.get_decimal().is_zero()
+}
+
 /// The ResultsVec is used so that a failure made "at some point" while
 /// fetching all of the addresses does not ruin all previous or preceding
 /// addresses
-type ResultsVec = Vec<(PublicKey, std::result::Result<Account, helium_api::Error>)>;
+pub type ResultsVec = Vec<(u8, PublicKey, Result<Account>)>;
 
-async fn print_balance(network: Network, results: &ResultsVec) -> Result {
+/// USD value of the liquid and staked HNT of an account
+fn hnt_value(price: &Price, account: &Account) -> String {
+    price.fmt_usd(account.balance.get_decimal() + account.staked_balance.get_decimal())
+}
+
+async fn print_balance(network: Network, results: &ResultsVec, price: Option<&Price>) -> Result {
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
     let balance = match network {
@@ -82,8 +178,8 @@ async fn print_balance(network: Network, results: &ResultsVec) -> Result {
         Network::MainNet => "Security Tokens",
     };
 
-    if results.len() > 1 {
-        table.set_titles(row![
+    let mut titles = if results.len() > 1 {
+        row![
             "Index",
             "Wallet",
             balance,
@@ -92,9 +188,9 @@ async fn print_balance(network: Network, results: &ResultsVec) -> Result {
             security_tokens,
             iot_balance,
             mobile_balance,
-        ]);
+        ]
     } else {
-        table.set_titles(row![
+        row![
             "Wallet 0",
             balance,
             staked_balance,
@@ -102,40 +198,82 @@ async fn print_balance(network: Network, results: &ResultsVec) -> Result {
             security_tokens,
             iot_balance,
             mobile_balance,
-        ]);
+        ]
+    };
+    if price.is_some() {
+        titles.add_cell(cell!("HNT Value USD"));
     }
-    for (account_index, (pubkey, result)) in results.iter().enumerate() {
+    table.set_titles(titles);
+    for (account_index, pubkey, result) in results.iter() {
         let address = pubkey.to_string();
         if results.len() > 1 {
             match result {
-                Ok(account) => table.add_row(row![
-                    account_index,
-                    address,
-                    account.balance,
-                    account.staked_balance,
-                    account.dc_balance,
-                    account.sec_balance,
-                    account.iot_balance,
-                    account.mobile_balance,
-                ]),
+                Ok(account) => {
+                    let mut row = row![
+                        account_index,
+                        address,
+                        account.balance,
+                        account.staked_balance,
+                        account.dc_balance,
+                        account.sec_balance,
+                        account.iot_balance,
+                        account.mobile_balance,
+                    ];
+                    if let Some(price) = price {
+                        row.add_cell(cell!(hnt_value(price, account)));
+                    }
+                    table.add_row(row)
+                }
                 Err(err) => table.add_row(row![account_index, address, H3 -> err.to_string()]),
             };
         } else {
             match result {
-                Ok(account) => table.add_row(row![
-                    address,
-                    account.balance,
-                    account.staked_balance,
-                    account.dc_balance,
-                    account.sec_balance,
-                    account.iot_balance,
-                    account.mobile_balance,
-                ]),
+                Ok(account) => {
+                    let mut row = row![
+                        address,
+                        account.balance,
+                        account.staked_balance,
+                        account.dc_balance,
+                        account.sec_balance,
+                        account.iot_balance,
+                        account.mobile_balance,
+                    ];
+                    if let Some(price) = price {
+                        row.add_cell(cell!(hnt_value(price, account)));
+                    }
+                    table.add_row(row)
+                }
                 Err(err) => table.add_row(row![address, H3 -> err.to_string()]),
             };
         }
     }
+    if results.len() > 1 {
+        let accounts: Vec<&Account> = results
+            .iter()
+            .filter_map(|(_, _, result)| result.as_ref().ok())
+            .collect();
+        let total = |f: fn(&Account) -> Decimal| accounts.iter().map(|a| f(*a)).sum::<Decimal>();
+        let mut row = row![
+            "",
+            "Total",
+            Hnt::new(total(|a| a.balance.get_decimal())),
+            Hnt::new(total(|a| a.staked_balance.get_decimal())),
+            accounts.iter().map(|a| a.dc_balance).sum::<u64>(),
+            Hst::new(total(|a| a.sec_balance.get_decimal())),
+            Iot::new(total(|a| a.iot_balance.get_decimal())),
+            Mobile::new(total(|a| a.mobile_balance.get_decimal())),
+        ];
+        if let Some(price) = price {
+            row.add_cell(cell!(price.fmt_usd(total(|a| {
+                a.balance.get_decimal() + a.staked_balance.get_decimal()
+            }))));
+        }
+        table.add_row(row);
+    }
 
     table.printstd();
+    if let Some(price) = price {
+        println!("Valued at {}", price);
+    }
     Ok(())
 }
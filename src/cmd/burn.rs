@@ -1,21 +1,32 @@
 use super::*;
+use helium_api::models::Account;
 use memo::Memo;
 use std::str::FromStr;
 
 #[derive(Debug, StructOpt)]
 /// Burn HNT to Data Credits (DC) from this wallet to given payees wallet.
 pub struct Cmd {
-    /// Account address to send the resulting DC to.
+    /// Account address to send the resulting DC to, or an @label from the
+    /// address book.
     #[structopt(long)]
-    payee: PublicKey,
+    payee: Address,
 
     /// Memo field to include. Provide as a base64 encoded string
     #[structopt(long, default_value)]
     memo: Memo,
 
     /// Amount of HNT to burn to DC
+    #[structopt(long, required_unless = "dc", conflicts_with = "dc")]
+    amount: Option<Hnt>,
+
+    /// Amount of DC to mint. The HNT to burn is computed from the current
+    /// oracle price, rounded up so that at least this many DC are minted
     #[structopt(long)]
-    amount: Hnt,
+    dc: Option<u64>,
+
+    /// Maximum age in blocks of the oracle price used with --dc
+    #[structopt(long, default_value = "60")]
+    max_price_age: u64,
 
     /// Manually set the nonce to use for the transaction
     #[structopt(long)]
@@ -32,7 +43,7 @@ impl Cmd {
             panic!("Upgrade the Helium Ledger App to use additional wallet accounts");
         };
 
-        match ledger(opts, self).await? {
+        match ledger(opts, &version, self).await? {
             Response::Txn(_txn, hash, network) => Ok(Some((hash, network))),
             Response::InsufficientHntBalance(balance, send_request) => {
                 println!(
@@ -77,30 +88,93 @@ impl Cmd {
     }
 }
 
-async fn ledger(opts: Opts, cmd: Cmd) -> Result<Response<BlockchainTxnTokenBurnV1>> {
+async fn ledger(
+    opts: Opts,
+    version: &Version,
+    cmd: Cmd,
+) -> Result<Response<BlockchainTxnTokenBurnV1>> {
     let ledger_transport = get_ledger_transport(&opts).await?;
-    let amount = cmd.amount;
-    let payee = cmd.payee;
 
     // get nonce
     let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
-    let client = new_client(pubkey.network);
-
-    let account = accounts::get(&client, &pubkey.to_string()).await?;
-    let nonce: u64 = if let Some(nonce) = cmd.nonce {
-        nonce
-    } else {
-        account.speculative_nonce + 1
-    };
 
-    if account.balance.get_decimal() < amount.get_decimal() {
+    let account = api::account(pubkey.network, &pubkey.to_string()).await?;
+    let (txn, oracle_price) = build_txn(&opts, &account, &cmd).await?;
+    if u64::from(account.balance) < txn.amount {
         return Ok(Response::InsufficientHntBalance(
             account.balance,
-            Hnt::new(amount.get_decimal()),
+            Hnt::from(txn.amount),
+        ));
+    }
+
+    preflight::check(version, &opts, &pubkey, std::slice::from_ref(&txn)).await?;
+    // the resulting DC are always shown at the oracle price the chain mints
+    // at, even if --price is given for the USD value
+    let oracle_price = match oracle_price {
+        Some(price) => price,
+        None => Price::current(pubkey.network).await?,
+    };
+    let usd_price = match opts.price {
+        Some(_) => Price::for_opts(&opts, pubkey.network).await?,
+        None if opts.usd => Some(oracle_price),
+        None => None,
+    };
+    print_proposed_txn(&txn, &oracle_price, usd_price.as_ref(), cmd.dc)?;
+
+    let adpu_cmd = txn.apdu_serialize(opts.account)?;
+
+    let exchange_pay_tx_result = read_from_ledger(&ledger_transport, adpu_cmd).await?;
+
+    if exchange_pay_tx_result.data.len() == 1 {
+        return Ok(Response::UserDeniedTransaction);
+    }
+    let data = exchange_pay_tx_result.data;
+    let txn = BlockchainTxnTokenBurnV1::decode(data.as_slice())?;
+
+    // submit the signed tansaction to the API
+    let pending_txn_status = submit_signed(&opts, version, &txn).await?;
+
+    Ok(Response::Txn(txn, pending_txn_status.hash, pubkey.network))
+}
+
+/// Builds the unsigned burn the given command describes, paid by the given
+/// account. Returns the oracle price the amount was computed from for --dc.
+pub(crate) async fn build_txn(
+    opts: &Opts,
+    account: &Account,
+    cmd: &Cmd,
+) -> Result<(BlockchainTxnTokenBurnV1, Option<Price>)> {
+    if cmd.dc == Some(0) || matches!(cmd.amount, Some(amount) if u64::from(amount) == 0) {
+        return Err(Error::Price(
+            "nothing to burn, give a positive --amount or --dc".to_string(),
         ));
     }
-    // serialize payer
     let payer = PublicKey::from_str(&account.address)?;
+    let payee = cmd.payee.resolve(payer.network)?;
+
+    // a DC target is always converted at the oracle price, since that is
+    // what the chain mints at
+    let (amount, oracle_price) = match (cmd.amount, cmd.dc) {
+        (_, Some(dc)) => {
+            if opts.price.is_some() {
+                println!(
+                    "WARNING: --price only sets the USD value. The HNT to burn for --dc is \
+                    computed at the oracle price"
+                );
+            }
+            let price = Price::current(payer.network).await?;
+            price.check_age(payer.network, cmd.max_price_age).await?;
+            (price.hnt_for_dc(dc)?, Some(price))
+        }
+        (Some(amount), None) => (amount, None),
+        (None, None) => return Err(Error::Price("give either --amount or --dc".to_string())),
+    };
+
+    let nonce: u64 = if let Some(nonce) = cmd.nonce {
+        nonce
+    } else {
+        account.speculative_nonce + 1
+    };
 
     let mut txn = BlockchainTxnTokenBurnV1 {
         payee: payee.to_vec(),
@@ -115,56 +189,64 @@ async fn ledger(opts: Opts, cmd: Cmd) -> Result<Response<BlockchainTxnTokenBurnV
         fee
     } else {
         txn.txn_fee(
-            &get_txn_fees(&client)
+            &get_txn_fees(payer.network)
                 .await
                 .map_err(|_| Error::getting_fees())?,
         )
         .map_err(|_| Error::getting_fees())?
     };
-
-    print_proposed_txn(&txn)?;
-
-    let adpu_cmd = txn.apdu_serialize(opts.account)?;
-
-    let exchange_pay_tx_result = read_from_ledger(&ledger_transport, adpu_cmd).await?;
-
-    if exchange_pay_tx_result.data.len() == 1 {
-        return Ok(Response::UserDeniedTransaction);
-    }
-    let data = exchange_pay_tx_result.data;
-    let txn = BlockchainTxnTokenBurnV1::decode(data.as_slice())?;
-
-    let envelope = txn.in_envelope();
-    // submit the signed tansaction to the API
-    let pending_txn_status = submit_txn(&client, &envelope).await?;
-
-    Ok(Response::Txn(txn, pending_txn_status.hash, payer.network))
+    Ok((txn, oracle_price))
 }
 
-pub fn print_proposed_txn(txn: &BlockchainTxnTokenBurnV1) -> Result {
+/// Prints the burn with the amount of DC it mints at the given oracle price,
+/// and its USD value at `usd_price` if given. `requested_dc` is the DC target
+/// the burn amount was computed from, if any.
+pub fn print_proposed_txn(
+    txn: &BlockchainTxnTokenBurnV1,
+    oracle_price: &Price,
+    usd_price: Option<&Price>,
+    requested_dc: Option<u64>,
+) -> Result {
     let payee = PublicKey::try_from(txn.payee.clone())?;
     let units = match payee.network {
         Network::TestNet => "TNT",
         Network::MainNet => "HNT",
     };
 
+    let amount = Hnt::from(txn.amount);
     let mut table = Table::new();
     println!("Creating the following transaction:");
-    table.add_row(row![
+    let mut titles = row![
         "Payee",
         &format!("Burn Amount {}", units),
+        "Resulting DC",
         "Memo",
         "Nonce",
         "DC Fee"
-    ]);
-    table.add_row(row![
-        payee,
-        Hnt::from(txn.amount),
+    ];
+    let mut values = row![
+        labeled(&payee),
+        amount,
+        oracle_price.dc_for_hnt(amount.get_decimal()),
         Memo::from(txn.memo),
         txn.nonce,
         txn.fee
-    ]);
+    ];
+    if let Some(dc) = requested_dc {
+        titles.insert_cell(2, cell!("Requested DC"));
+        values.insert_cell(2, cell!(dc));
+    }
+    if let Some(price) = usd_price {
+        titles.add_cell(cell!("Value USD"));
+        values.add_cell(cell!(price.fmt_usd(amount.get_decimal())));
+    }
+    table.add_row(titles);
+    table.add_row(values);
     table.printstd();
+    println!("Resulting DC estimated at {}", oracle_price);
+    if let Some(price) = usd_price.filter(|price| price.block.is_none()) {
+        println!("USD value at {}", price);
+    }
     println!(
         "WARNING: do not use this output as the source of truth. Instead, rely \
     on the Ledger Display"
@@ -0,0 +1,44 @@
+use super::*;
+use crate::{journal, partial};
+use helium_crypto::Verify;
+
+#[derive(Debug, StructOpt)]
+/// Decode a partially signed transaction container or a base64 encoded
+/// transaction and show its fields and signatures. Containers are verified
+/// against the enclosed transaction.
+pub struct Cmd {
+    /// File to decode, - for stdin, or a base64 encoded transaction
+    #[structopt(name = "FILE|TRANSACTION")]
+    input: String,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let (envelope, container) = partial::parse(&read_input(&self.input)?)?;
+        if let Some(container) = &container {
+            container.print();
+            println!("Container verified against the enclosed transaction");
+        }
+
+        let txn = journal::from_envelope(&envelope)?;
+        let data = txn.signing_data()?;
+        let mut table = Table::new();
+        table.add_row(row!["Role", "Address", "Signature"]);
+        for signer in txn.signers()? {
+            let signature = if !signer.has_signed() {
+                "missing"
+            } else if signer.key.verify(&data, &signer.signature).is_ok() {
+                "valid"
+            } else {
+                "INVALID"
+            };
+            table.add_row(row![signer.role, labeled(&signer.key), signature]);
+        }
+        table.printstd();
+
+        println!("Type:   {}", txn.txn_type());
+        println!("Hash:   {}", txn.hash()?);
+        println!("Fields:\n{}", serde_json::to_string_pretty(&txn.fields()?)?);
+        Ok(None)
+    }
+}
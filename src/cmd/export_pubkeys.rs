@@ -0,0 +1,149 @@
+use super::*;
+use serde::Serialize;
+use serde_json::json;
+use std::{io::Write, path::PathBuf, str::FromStr};
+
+#[derive(Debug, StructOpt)]
+/// Export the public keys of a range of Ledger accounts for watch-only use,
+/// such as monitoring. Nothing exported can sign.
+///
+/// The watch-only format is specific to this application: a JSON object
+/// {"watch_only": true, "keys": [...]} listing the address, network,
+/// key_type and a ledger-<account> label of each key.
+pub struct Cmd {
+    /// Accounts to export, as a range such as 0-9 or a list such as 0,2,5-7
+    #[structopt(long, default_value = "0")]
+    accounts: Accounts,
+
+    /// Export format: json, csv or watch-only
+    #[structopt(long, default_value = "json")]
+    format: PubkeyFormat,
+
+    /// File to write the export to. Standard output is used if not given
+    #[structopt(long)]
+    output: Option<PathBuf>,
+
+    /// Display each key on the Ledger for confirmation while exporting
+    #[structopt(long)]
+    verify: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PubkeyFormat {
+    Json,
+    Csv,
+    WatchOnly,
+}
+
+impl FromStr for PubkeyFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(PubkeyFormat::Json),
+            "csv" => Ok(PubkeyFormat::Csv),
+            "watch-only" => Ok(PubkeyFormat::WatchOnly),
+            _ => Err(Error::ExportFormat(s.to_string())),
+        }
+    }
+}
+
+/// Ledger account indices, parsed from ranges such as 0-9 or lists such as
+/// 0,2,5-7
+#[derive(Debug, Clone)]
+pub struct Accounts(Vec<u8>);
+
+impl FromStr for Accounts {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::AccountRange(s.to_string());
+        let mut accounts = vec![];
+        for part in s.split(',').map(str::trim) {
+            let (start, end) = match part.split_once('-') {
+                Some((start, end)) => (start, end),
+                None => (part, part),
+            };
+            let start: u8 = start.trim().parse().map_err(|_| invalid())?;
+            let end: u8 = end.trim().parse().map_err(|_| invalid())?;
+            if start > end {
+                return Err(invalid());
+            }
+            accounts.extend(start..=end);
+        }
+        accounts.sort_unstable();
+        accounts.dedup();
+        Ok(Accounts(accounts))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Record {
+    account: u8,
+    network: String,
+    address: String,
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        // additional wallet accounts need version 2 of the Helium app
+        if version.major < 2 && self.accounts.0.iter().any(|&account| account != 0) {
+            return Err(Error::UnsupportedLedgerVersion);
+        }
+
+        let ledger_transport = get_ledger_transport(&opts).await?;
+        let mut records = Vec::with_capacity(self.accounts.0.len());
+        for &account in &self.accounts.0 {
+            let pubkey = get_pubkey(account, &ledger_transport, PubkeyDisplay::Off).await?;
+            if self.verify {
+                eprintln!(
+                    "Confirm that the Ledger displays {} for account {}",
+                    pubkey, account
+                );
+                let displayed = get_pubkey(account, &ledger_transport, PubkeyDisplay::On).await?;
+                if displayed != pubkey {
+                    return Err(Error::AddressMismatch(format!(
+                        "the Ledger displayed {} for account {} but {} was derived before",
+                        displayed, account, pubkey
+                    )));
+                }
+            }
+            records.push(Record {
+                account,
+                network: pubkey.network.to_string(),
+                address: pubkey.to_string(),
+            });
+        }
+
+        let output = self.output.as_deref();
+        match self.format {
+            PubkeyFormat::Json => write_export(ExportFormat::Json, output, &records)?,
+            PubkeyFormat::Csv => write_export(ExportFormat::Csv, output, &records)?,
+            PubkeyFormat::WatchOnly => {
+                let keys: Vec<_> = records
+                    .iter()
+                    .map(|record| {
+                        json!({
+                            "address": record.address,
+                            "network": record.network,
+                            "key_type": "ed25519",
+                            "label": format!("ledger-{}", record.account),
+                        })
+                    })
+                    .collect();
+                let export = json!({ "watch_only": true, "keys": keys });
+                let mut writer = export_writer(output)?;
+                serde_json::to_writer_pretty(&mut writer, &export)?;
+                writeln!(writer)?;
+            }
+        }
+        if let Some(path) = &self.output {
+            println!(
+                "Exported {} public keys to {}",
+                records.len(),
+                path.display()
+            );
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,107 @@
+use super::*;
+use crate::cmd::validator::{stake, transfer, unstake};
+
+#[derive(Debug, StructOpt)]
+/// Quote the fee of a transaction without signing it. The transaction is
+/// built exactly as the given command would build it, and takes the same
+/// arguments. The Ledger is only used to derive the account address when
+/// --address is not given.
+pub struct Cmd {
+    /// Account to quote the fee for. Defaults to the selected Ledger account
+    #[structopt(long)]
+    address: Option<PublicKey>,
+
+    #[structopt(subcommand)]
+    txn: Txn,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+pub enum Txn {
+    /// Quote a payment
+    Pay(pay::Cmd),
+    /// Quote a burn
+    Burn(burn::Cmd),
+    /// Quote a validator stake
+    Stake(stake::Validator),
+    /// Quote a validator unstake
+    Unstake(unstake::Cmd),
+    /// Quote a validator transfer
+    Transfer(transfer::Create),
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts) -> Result<Option<(String, Network)>> {
+        let account = match self.address {
+            Some(address) => address,
+            None => {
+                eprintln!("Communicating with Ledger - follow prompts on screen");
+                let ledger_transport = get_ledger_transport(&opts).await?;
+                get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?
+            }
+        };
+
+        match self.txn {
+            Txn::Pay(pay) => {
+                let chain = api::account(account.network, &account.to_string()).await?;
+                let txn = pay::build_txn(&account, &chain, &pay).await?;
+                quote(&txn).await
+            }
+            Txn::Burn(burn) => {
+                let chain = api::account(account.network, &account.to_string()).await?;
+                let (txn, _) = burn::build_txn(&opts, &chain, &burn).await?;
+                quote(&txn).await
+            }
+            Txn::Stake(validator) => {
+                let address = validator.address.resolve(account.network)?;
+                let txn = stake::build_txn(&account, &address, validator.stake).await?;
+                quote(&txn).await
+            }
+            Txn::Unstake(unstake) => {
+                let txn = unstake.build_txn(&account).await?;
+                quote(&txn).await
+            }
+            Txn::Transfer(transfer) => {
+                let (old_owner, new_owner) = transfer.owners(&account)?;
+                let txn = transfer.build_txn(&old_owner, &new_owner).await?;
+                quote(&txn).await
+            }
+        }?;
+        Ok(None)
+    }
+}
+
+/// Prints the fee of a transaction, its HNT equivalent at the oracle price,
+/// and whether the DC balance of the fee payer covers it
+async fn quote<T: Journaled>(txn: &T) -> Result {
+    let (payer, fee) = txn.fee()?;
+    let price = Price::current(payer.network).await?;
+    let dc_balance = api::account(payer.network, &payer.to_string())
+        .await?
+        .dc_balance;
+    let covered = dc_balance >= fee;
+
+    let mut table = Table::new();
+    table.add_row(row![
+        "Transaction",
+        "Fee Payer",
+        "DC Fee",
+        "HNT Equivalent",
+        "DC Balance",
+        "Covered by DC"
+    ]);
+    table.add_row(row![
+        txn.txn_type(),
+        labeled(&payer),
+        fee,
+        price.hnt_for_dc(fee)?,
+        dc_balance,
+        if covered { "yes" } else { "no" }
+    ]);
+    table.printstd();
+    println!("HNT equivalent at {}", price);
+    if !covered {
+        println!("The DC balance does not cover the fee, so HNT will be burned to pay for it");
+    }
+    Ok(())
+}
@@ -0,0 +1,332 @@
+use super::*;
+use crate::{api, cmd::pay::TokenInput};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::{path::PathBuf, str::FromStr};
+
+#[derive(Debug, StructOpt)]
+/// Show the activity of a Ledger account for accounting and tax reporting.
+/// Every transaction type the Ledger can sign is listed, as well as rewards
+/// and incoming payments.
+pub struct Cmd {
+    /// Only include activity at or after this date (YYYY-MM-DD or RFC 3339)
+    #[structopt(long)]
+    since: Option<Date>,
+    /// Only include activity before this date (YYYY-MM-DD or RFC 3339)
+    #[structopt(long)]
+    until: Option<Date>,
+    /// Export format (json or csv) instead of a table
+    #[structopt(long)]
+    format: Option<ExportFormat>,
+    /// File to write the export to. Standard output is used if not given.
+    /// Requires --format
+    #[structopt(long, requires = "format")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Date(DateTime<Utc>);
+
+impl FromStr for Date {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Date(Utc.from_utc_datetime(&date.and_hms(0, 0, 0))));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|date| Date(date.with_timezone(&Utc)))
+            .map_err(|_| Error::Date(s.to_string()))
+    }
+}
+
+/// A single normalized line of account activity. Amounts are signed from the
+/// point of view of the account: tokens leaving the account are negative.
+#[derive(Debug, Serialize)]
+struct Record {
+    timestamp: String,
+    hash: String,
+    #[serde(rename = "type")]
+    txn_type: String,
+    counterparty: String,
+    token: String,
+    amount: String,
+    fee: u64,
+    memo: String,
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        let ledger_transport = get_ledger_transport(&opts).await?;
+        let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
+        let account = pubkey.to_string();
+
+        let since = self.since.map(|date| date.0.to_rfc3339());
+        let until = self.until.map(|date| date.0.to_rfc3339());
+        let mut query = Vec::new();
+        if let Some(since) = &since {
+            query.push(("min_time", since.as_str()));
+        }
+        if let Some(until) = &until {
+            query.push(("max_time", until.as_str()));
+        }
+        let activity = api::get_all(
+            version.network,
+            &format!("/accounts/{}/activity", account),
+            &query,
+        )
+        .await?;
+
+        let mut records = Vec::new();
+        for txn in &activity {
+            records.extend(normalize(&account, txn));
+        }
+
+        match self.format {
+            None => print_records(&records),
+            Some(format) => write_export(format, self.output.as_deref(), &records)?,
+        }
+        Ok(None)
+    }
+}
+
+fn print_records(records: &[Record]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row![
+        "Time",
+        "Type",
+        "Counterparty",
+        "Token",
+        "Amount",
+        "DC Fee",
+        "Memo",
+        "Hash"
+    ]);
+    for record in records {
+        table.add_row(row![
+            record.timestamp,
+            record.txn_type,
+            record.counterparty,
+            record.token,
+            record.amount,
+            record.fee,
+            record.memo,
+            record.hash,
+        ]);
+    }
+    table.printstd();
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> &'a str {
+    value.get(field).and_then(Value::as_str).unwrap_or("")
+}
+
+fn u64_field(value: &Value, field: &str) -> u64 {
+    value.get(field).and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn signed(amount: u64, outgoing: bool) -> String {
+    if outgoing {
+        format!("-{}", Token::from(amount))
+    } else {
+        Token::from(amount).to_string()
+    }
+}
+
+/// Turns an activity entry from the API into records for the given account.
+/// Unsupported transaction types produce no records.
+fn normalize(account: &str, txn: &Value) -> Vec<Record> {
+    let txn_type = str_field(txn, "type");
+    let timestamp = Utc
+        .timestamp_opt(u64_field(txn, "time") as i64, 0)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default();
+    let record =
+        |counterparty: &str, token: TokenInput, amount: String, fee: u64, memo: &str| Record {
+            timestamp: timestamp.clone(),
+            hash: str_field(txn, "hash").to_string(),
+            txn_type: txn_type.to_string(),
+            counterparty: counterparty.to_string(),
+            token: token.to_string(),
+            amount,
+            fee,
+            memo: memo.to_string(),
+        };
+    let fee = u64_field(txn, "fee");
+
+    match txn_type {
+        "payment_v1" => {
+            let outgoing = str_field(txn, "payer") == account;
+            let counterparty = if outgoing {
+                str_field(txn, "payee")
+            } else {
+                str_field(txn, "payer")
+            };
+            vec![record(
+                counterparty,
+                TokenInput::Hnt,
+                signed(u64_field(txn, "amount"), outgoing),
+                if outgoing { fee } else { 0 },
+                "",
+            )]
+        }
+        "payment_v2" => {
+            let payer = str_field(txn, "payer");
+            let outgoing = payer == account;
+            let payments = txn
+                .get("payments")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            payments
+                .iter()
+                .filter(|payment| outgoing || str_field(payment, "payee") == account)
+                .enumerate()
+                .map(|(index, payment)| {
+                    let token = TokenInput::from_str(str_field(payment, "token_type"))
+                        .unwrap_or(TokenInput::Hnt);
+                    record(
+                        if outgoing {
+                            str_field(payment, "payee")
+                        } else {
+                            payer
+                        },
+                        token,
+                        signed(u64_field(payment, "amount"), outgoing),
+                        // the fee is paid once per transaction
+                        if outgoing && index == 0 { fee } else { 0 },
+                        str_field(payment, "memo"),
+                    )
+                })
+                .collect()
+        }
+        "token_burn_v1" => {
+            let outgoing = str_field(txn, "payer") == account;
+            vec![record(
+                if outgoing {
+                    str_field(txn, "payee")
+                } else {
+                    str_field(txn, "payer")
+                },
+                TokenInput::Hnt,
+                // a burn to this account only credits DC, which has no amount
+                // in HNT for this account
+                if outgoing {
+                    signed(u64_field(txn, "amount"), true)
+                } else {
+                    Token::from(0u64).to_string()
+                },
+                if outgoing { fee } else { 0 },
+                str_field(txn, "memo"),
+            )]
+        }
+        "stake_validator_v1" => vec![record(
+            str_field(txn, "address"),
+            TokenInput::Hnt,
+            signed(u64_field(txn, "stake"), true),
+            fee,
+            "",
+        )],
+        "unstake_validator_v1" => vec![record(
+            str_field(txn, "address"),
+            TokenInput::Hnt,
+            signed(u64_field(txn, "stake_amount"), false),
+            fee,
+            "",
+        )],
+        "transfer_validator_stake_v1" => {
+            let old_owner = str_field(txn, "old_owner");
+            let new_owner = str_field(txn, "new_owner");
+            let payment = u64_field(txn, "payment_amount");
+            if old_owner == new_owner {
+                vec![record(
+                    str_field(txn, "new_address"),
+                    TokenInput::Hnt,
+                    Token::from(0u64).to_string(),
+                    fee,
+                    "",
+                )]
+            } else if old_owner == account {
+                vec![record(
+                    new_owner,
+                    TokenInput::Hnt,
+                    signed(payment, false),
+                    fee,
+                    "",
+                )]
+            } else {
+                vec![record(
+                    old_owner,
+                    TokenInput::Hnt,
+                    signed(payment, true),
+                    0,
+                    "",
+                )]
+            }
+        }
+        "rewards_v1" | "rewards_v2" => {
+            // security rewards are paid in HST, every other reward in HNT
+            let (securities, rewards): (Vec<&Value>, Vec<&Value>) = account_rewards(txn, account)
+                .partition(|reward| str_field(reward, "type") == "securities");
+            let sum = |rewards: &[&Value]| -> u64 {
+                rewards
+                    .iter()
+                    .map(|reward| u64_field(reward, "amount"))
+                    .sum()
+            };
+            let mut records = vec![record(
+                "rewards",
+                TokenInput::Hnt,
+                signed(sum(&rewards), false),
+                0,
+                "",
+            )];
+            if !securities.is_empty() {
+                records.push(record(
+                    "rewards",
+                    TokenInput::Hst,
+                    signed(sum(&securities), false),
+                    0,
+                    "",
+                ));
+            }
+            records
+        }
+        "subnetwork_rewards_v1" => {
+            let amount: u64 = account_rewards(txn, account)
+                .map(|reward| u64_field(reward, "amount"))
+                .sum();
+            match TokenInput::from_str(str_field(txn, "token_type")) {
+                Ok(token) => vec![record("rewards", token, signed(amount, false), 0, "")],
+                Err(_) => vec![],
+            }
+        }
+        "security_exchange_v1" => {
+            let outgoing = str_field(txn, "payer") == account;
+            vec![record(
+                if outgoing {
+                    str_field(txn, "payee")
+                } else {
+                    str_field(txn, "payer")
+                },
+                TokenInput::Hst,
+                signed(u64_field(txn, "amount"), outgoing),
+                if outgoing { fee } else { 0 },
+                "",
+            )]
+        }
+        _ => vec![],
+    }
+}
+
+/// The rewards of a rewards transaction paid to the given account
+fn account_rewards<'a>(txn: &'a Value, account: &'a str) -> impl Iterator<Item = &'a Value> {
+    txn.get("rewards")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(move |reward| str_field(reward, "account") == account)
+}
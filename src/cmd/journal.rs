@@ -0,0 +1,178 @@
+use super::*;
+use crate::journal::{self as store, Entry, Status};
+use serde::Serialize;
+use std::{collections::HashSet, path::PathBuf};
+
+#[derive(Debug, StructOpt)]
+/// Inspect the local journal of every transaction signed with this
+/// application. Use it to reconcile against the chain or to recover signed
+/// transactions that never made it to the API.
+pub enum Cmd {
+    /// List journaled transactions, oldest first
+    List(List),
+    /// Show all journal entries for a given transaction hash
+    Show(Show),
+    /// Export the journal as JSON or CSV
+    Export(Export),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct List {
+    /// Only list the most recent entries
+    #[structopt(long)]
+    limit: Option<usize>,
+    /// Only list transactions that were never successfully submitted
+    #[structopt(long)]
+    unsubmitted: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Show {
+    /// Hash of the transaction to show
+    hash: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    /// Output format (json or csv)
+    #[structopt(long, default_value = "json")]
+    format: ExportFormat,
+    /// File to write to. Standard output is used if not given
+    #[structopt(long)]
+    output: Option<PathBuf>,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let entries = store::entries()?;
+        match self {
+            Cmd::List(list) => list.run(entries),
+            Cmd::Show(show) => show.run(entries)?,
+            Cmd::Export(export) => export.run(entries)?,
+        }
+        Ok(None)
+    }
+}
+
+impl List {
+    fn run(self, mut entries: Vec<Entry>) {
+        if self.unsubmitted {
+            let submitted: HashSet<String> = entries
+                .iter()
+                .filter(|entry| entry.status == Status::Submitted)
+                .map(|entry| entry.hash.clone())
+                .collect();
+            entries.retain(|entry| !submitted.contains(&entry.hash));
+        }
+        if let Some(limit) = self.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(row!["Time", "Account", "Network", "Type", "Hash", "Status"]);
+        for entry in &entries {
+            table.add_row(row![
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                account_name(entry.account),
+                entry.network,
+                entry.txn_type,
+                entry.hash,
+                status_name(entry.status),
+            ]);
+        }
+        table.printstd();
+    }
+}
+
+impl Show {
+    fn run(self, entries: Vec<Entry>) -> Result {
+        let entries: Vec<Entry> = entries
+            .into_iter()
+            .filter(|entry| entry.hash == self.hash)
+            .collect();
+        let last = entries
+            .last()
+            .ok_or_else(|| Error::Journal(format!("no journal entry for hash {}", self.hash)))?;
+
+        let mut table = Table::new();
+        table.add_row(row!["Time", "Status", "Response"]);
+        for entry in &entries {
+            table.add_row(row![
+                entry.timestamp.to_rfc3339(),
+                status_name(entry.status),
+                entry.response.as_deref().unwrap_or(""),
+            ]);
+        }
+        table.printstd();
+
+        println!("Type:        {}", last.txn_type);
+        println!("Account:     {}", account_name(last.account));
+        println!("Network:     {}", last.network);
+        println!(
+            "App version: {}",
+            last.app_version.as_deref().unwrap_or("none")
+        );
+        println!("Fields:\n{}", serde_json::to_string_pretty(&last.fields)?);
+        println!("Transaction:\n{}", last.txn);
+        Ok(())
+    }
+}
+
+/// A flattened journal entry for CSV export
+#[derive(Serialize)]
+struct CsvEntry<'a> {
+    timestamp: String,
+    account: Option<u8>,
+    network: &'a str,
+    app_version: Option<&'a str>,
+    txn_type: &'a str,
+    hash: &'a str,
+    status: &'static str,
+    response: &'a str,
+    fields: String,
+    txn: &'a str,
+}
+
+impl Export {
+    fn run(self, entries: Vec<Entry>) -> Result {
+        let output = self.output.as_deref();
+        match self.format {
+            ExportFormat::Json => write_export(self.format, output, &entries),
+            ExportFormat::Csv => {
+                let rows: Vec<CsvEntry> = entries
+                    .iter()
+                    .map(|entry| CsvEntry {
+                        timestamp: entry.timestamp.to_rfc3339(),
+                        account: entry.account,
+                        network: &entry.network,
+                        app_version: entry.app_version.as_deref(),
+                        txn_type: &entry.txn_type,
+                        hash: &entry.hash,
+                        status: status_name(entry.status),
+                        response: entry.response.as_deref().unwrap_or(""),
+                        fields: entry.fields.to_string(),
+                        txn: &entry.txn,
+                    })
+                    .collect();
+                write_export(self.format, output, &rows)
+            }
+        }
+    }
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Signed => "signed",
+        Status::Submitted => "submitted",
+        Status::Failed => "failed",
+    }
+}
+
+/// The Ledger account of an entry, or "none" for transactions signed
+/// elsewhere
+fn account_name(account: Option<u8>) -> String {
+    account
+        .map(|account| account.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
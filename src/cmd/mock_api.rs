@@ -0,0 +1,57 @@
+use super::*;
+use crate::mock::{Fixtures, MockApi};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+};
+
+#[derive(Debug, StructOpt)]
+/// Run a local mock of the Helium API serving fixture accounts, validators,
+/// chain vars and oracle prices, and recording submitted transactions. Point
+/// the application at it with HELIUM_API_URL or HELIUM_TESTNET_API_URL to
+/// run it without network access.
+///
+/// Failures can be injected while it runs by posting a JSON object such as
+/// {"path": "/pending_transactions", "status": 503, "count": 2} to
+/// /v1/mock/failures. Submitted transactions are listed at
+/// /v1/mock/submissions.
+pub struct Cmd {
+    /// Port to listen on
+    #[structopt(long, default_value = "4000")]
+    port: u16,
+
+    /// JSON file with the fixtures to serve: height, oracle_price, vars,
+    /// accounts and validators
+    #[structopt(long)]
+    fixtures: Option<PathBuf>,
+
+    /// File to append submitted transactions to, one JSON object per line
+    #[structopt(long)]
+    record: Option<PathBuf>,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let fixtures = match &self.fixtures {
+            Some(path) => Fixtures::load(path)?,
+            None => Fixtures::default(),
+        };
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), self.port);
+        let mock = MockApi::start(fixtures, addr)?;
+        if let Some(record) = self.record {
+            mock.record_to(record);
+        }
+        println!("Mock Helium API listening at {}", mock.url());
+        println!(
+            "Use it with HELIUM_API_URL={}. Stop with Ctrl-C",
+            mock.url()
+        );
+
+        tokio::signal::ctrl_c().await?;
+        println!(
+            "Stopped after {} submitted transactions",
+            mock.submissions().len()
+        );
+        Ok(None)
+    }
+}
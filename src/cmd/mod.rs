@@ -1,9 +1,9 @@
+pub use crate::addressbook::{labeled, Address};
+pub use crate::journal::Journaled;
+pub use crate::price::Price;
 use crate::*;
 use byteorder::{LittleEndian as LE, WriteBytesExt};
-use helium_api::{
-    accounts,
-    models::{Hnt, Hst, Iot, Mobile, Token},
-};
+use helium_api::models::{Hnt, Hst, Iot, Mobile, Token};
 pub use helium_proto::{
     BlockchainTxnPaymentV1, BlockchainTxnPaymentV2, BlockchainTxnSecurityExchangeV1,
     BlockchainTxnStakeValidatorV1, BlockchainTxnTokenBurnV1, BlockchainTxnTransferValidatorStakeV1,
@@ -17,12 +17,28 @@ pub use ledger_transport::*;
 pub use prost::Message;
 use std::convert::TryFrom;
 
+pub mod addressbook;
 pub mod balance;
 pub mod burn;
+pub mod decode;
+pub mod export_pubkeys;
+pub mod fee;
+pub mod history;
+pub mod journal;
+#[cfg(feature = "mock-api")]
+pub mod mock_api;
 pub mod pay;
+pub mod pending;
+pub mod preflight;
+pub mod price;
+pub mod receive;
+pub mod resubmit;
 pub mod serializer;
+pub mod submit;
 pub mod validator;
+pub mod vars;
 
+pub use preflight::Preflight;
 pub use serializer::*;
 
 const RETURN_CODE_OK: u16 = 0x9000;
@@ -77,6 +93,72 @@ pub async fn get_pubkey(
     Ok(PublicKey::try_from(&public_key_result.data[1..34])?)
 }
 
+/// Reads a command input given as a file path, as - for stdin, or directly
+/// as the argument itself
+pub fn read_input(input: &str) -> Result<String> {
+    use std::io::Read;
+    if input == "-" {
+        let mut buffer = String::new();
+        std::io::stdin().read_to_string(&mut buffer)?;
+        Ok(buffer)
+    } else if std::path::Path::new(input).is_file() {
+        Ok(std::fs::read_to_string(input)?)
+    } else {
+        Ok(input.to_string())
+    }
+}
+
+/// File formats for exported data
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(Error::ExportFormat(s.to_string())),
+        }
+    }
+}
+
+/// Opens the file to export to, or standard output if there is none
+pub fn export_writer(output: Option<&std::path::Path>) -> Result<Box<dyn std::io::Write>> {
+    Ok(match output {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    })
+}
+
+/// Writes records as JSON or CSV to the given file, or to standard output
+pub fn write_export<T: serde::Serialize>(
+    format: ExportFormat,
+    output: Option<&std::path::Path>,
+    records: &[T],
+) -> Result {
+    use std::io::Write;
+    let mut writer = export_writer(output)?;
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, records)?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
 pub enum Response<T> {
     Txn(T, String, Network),
     InsufficientHntBalance(Hnt, Hnt),
@@ -102,8 +184,57 @@ pub async fn read_from_ledger(
     }
 }
 
-pub async fn get_txn_fees(client: &Client) -> Result<TxnFeeConfig> {
-    let vars = helium_api::vars::get(client).await?;
+/// Submits a transaction signed by the Ledger to the API. The signed
+/// transaction is saved in the local journal before it is submitted, so that
+/// it can be resubmitted if the API fails, and the outcome is recorded after.
+pub async fn submit_signed<T: Journaled>(
+    opts: &Opts,
+    version: &Version,
+    txn: &T,
+) -> Result<PendingTxnStatus> {
+    submit_signed_by(&[opts.account], version, txn).await
+}
+
+/// Like `submit_signed`, for a transaction signed by the given Ledger
+/// accounts. It is journaled for each of them.
+pub async fn submit_signed_by<T: Journaled>(
+    accounts: &[u8],
+    version: &Version,
+    txn: &T,
+) -> Result<PendingTxnStatus> {
+    use crate::journal::{record, Status};
+    for account in accounts {
+        record(*account, version, txn, Status::Signed, None)?;
+    }
+    let result = submit_txn_with_retry(version.network, &txn.envelope()).await;
+    for account in accounts {
+        let recorded = match &result {
+            Ok(status) => record(
+                *account,
+                version,
+                txn,
+                Status::Submitted,
+                Some(status.hash.clone()),
+            ),
+            Err(err) => record(
+                *account,
+                version,
+                txn,
+                Status::Failed,
+                Some(err.to_string()),
+            ),
+        };
+        // the outcome of the submission matters more than the journal, which
+        // must not turn an accepted transaction into an error
+        if let Err(err) = recorded {
+            println!("WARNING: unable to journal the submission: {}", err);
+        }
+    }
+    result
+}
+
+pub async fn get_txn_fees(network: Network) -> Result<TxnFeeConfig> {
+    let vars = crate::vars::get(network).await?;
     if vars.contains_key("txn_fees") {
         match vars["txn_fees"].as_bool() {
             Some(true) => {
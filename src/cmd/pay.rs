@@ -1,11 +1,11 @@
 use super::*;
-use crate::memo::Memo;
+use crate::{memo::Memo, payment_request::PaymentRequest};
 use helium_api::models::Account;
 use helium_proto::BlockchainTokenTypeV1;
 use serde::Deserialize;
 use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TokenInput {
     Hnt,
     Iot,
@@ -15,16 +15,21 @@ pub enum TokenInput {
 
 #[derive(Debug, StructOpt)]
 pub struct Cmd {
-    /// Address to send the tokens to
-    address: PublicKey,
+    /// Address to send the tokens to, or an @label from the address book
+    #[structopt(required_unless = "uri", conflicts_with = "uri")]
+    address: Option<Address>,
     /// Amount of token to send
-    amount: Token,
-    /// Type of token to send (hnt, iot, mobile, hst).
-    #[structopt(default_value = "hnt")]
-    token: TokenInput,
+    #[structopt(required_unless = "uri", conflicts_with = "uri")]
+    amount: Option<Token>,
+    /// Type of token to send (hnt, iot, mobile, hst). Defaults to hnt
+    token: Option<TokenInput>,
     /// Memo field to include. Provide as a base64 encoded string
-    #[structopt(long, default_value = "AAAAAAAAAAA=")]
-    memo: Memo,
+    #[structopt(long, conflicts_with = "uri")]
+    memo: Option<Memo>,
+    /// Payment request URI such as helium:<address>?amount=1&token=hnt&memo=<base64>
+    /// to take the address, amount, token and memo from
+    #[structopt(long)]
+    uri: Option<PaymentRequest>,
     /// Manually set the DC fee to pay for the transaction
     #[structopt(long)]
     fee: Option<u64>,
@@ -44,7 +49,7 @@ impl Cmd {
             || version.major == 2 && version.minor > 2
             || version.major == 2 && version.minor == 2 && version.revision >= 3
         {
-            match ledger_v2(opts, self).await? {
+            match ledger_v2(opts, &version, self).await? {
                 Response::Txn(_txn, hash, network) => Ok(Some((hash, network))),
                 Response::InsufficientHntBalance(balance, send_request) => {
                     println!(
@@ -92,32 +97,104 @@ impl Cmd {
     }
 }
 
-async fn ledger_v2(opts: Opts, cmd: Cmd) -> Result<Response<BlockchainTxnPaymentV2>> {
+impl Cmd {
+    /// The payee, amount, token and memo to pay, taken from --uri or the
+    /// arguments
+    fn payment(&self, network: Network) -> Result<(PublicKey, Token, TokenInput, u64)> {
+        let memo = |memo: &Option<Memo>| memo.as_ref().map(u64::from).unwrap_or_default();
+        if let Some(request) = &self.uri {
+            request.check_network(network)?;
+            if self.token.is_some() {
+                return Err(Error::PaymentRequest(
+                    "give the token in the URI only".to_string(),
+                ));
+            }
+            let amount = request.amount.ok_or_else(|| {
+                Error::PaymentRequest("the payment request has no amount".to_string())
+            })?;
+            return Ok((
+                request.address.clone(),
+                amount,
+                request.token,
+                memo(&request.memo),
+            ));
+        }
+        match (&self.address, self.amount) {
+            (Some(address), Some(amount)) => Ok((
+                address.resolve(network)?,
+                amount,
+                self.token.unwrap_or(TokenInput::Hnt),
+                memo(&self.memo),
+            )),
+            _ => Err(Error::PaymentRequest(
+                "give an address and amount, or --uri".to_string(),
+            )),
+        }
+    }
+}
+
+async fn ledger_v2(
+    opts: Opts,
+    version: &Version,
+    cmd: Cmd,
+) -> Result<Response<BlockchainTxnPaymentV2>> {
     let ledger_transport = get_ledger_transport(&opts).await?;
-    let amount = cmd.amount;
-    let payee = cmd.address;
 
     // get nonce
     let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
-    let client = new_client(pubkey.network);
 
-    let account = accounts::get(&client, &pubkey.to_string()).await?;
+    if let Some(request) = &cmd.uri {
+        println!("Decoded payment request:");
+        request.print();
+    }
+    let (_, amount, token, _) = cmd.payment(pubkey.network)?;
+    let account = api::account(pubkey.network, &pubkey.to_string()).await?;
+    if let Some(response) = invalid_balance_response(&token, &account, amount) {
+        return Ok(response);
+    }
+    let txn = build_txn(&pubkey, &account, &cmd).await?;
+
+    preflight::check(version, &opts, &pubkey, std::slice::from_ref(&txn)).await?;
+    let price = Price::for_opts(&opts, pubkey.network).await?;
+    print_proposed_txn_v2(&txn, price.as_ref())?;
+
+    let adpu_cmd = txn.apdu_serialize(opts.account)?;
+
+    let exchange_pay_tx_result = read_from_ledger(&ledger_transport, adpu_cmd).await?;
+
+    if exchange_pay_tx_result.data.len() == 1 {
+        return Ok(Response::UserDeniedTransaction);
+    }
+
+    let txn = BlockchainTxnPaymentV2::decode(exchange_pay_tx_result.data.as_slice())?;
+    let payer = PublicKey::from_bytes(&txn.payer)?;
+
+    // submit the signed tansaction to the API
+    let pending_txn_status = submit_signed(&opts, version, &txn).await?;
+
+    Ok(Response::Txn(txn, pending_txn_status.hash, payer.network))
+}
+
+/// Builds the unsigned payment the given command describes, paid by `payer`
+/// whose chain state is `account`
+pub(crate) async fn build_txn(
+    payer: &PublicKey,
+    account: &Account,
+    cmd: &Cmd,
+) -> Result<BlockchainTxnPaymentV2> {
+    let (payee, amount, token, memo) = cmd.payment(payer.network)?;
     let nonce: u64 = if let Some(nonce) = cmd.nonce {
         nonce
     } else {
         account.speculative_nonce + 1
     };
 
-    if let Some(response) = invalid_balance_response(&cmd.token, &account, amount) {
-        return Ok(response);
-    }
-
     let payment = Payment {
         payee: payee.to_vec(),
         amount: u64::from(amount),
-        memo: u64::from(&cmd.memo),
+        memo,
         max: false,
-        token_type: match cmd.token {
+        token_type: match token {
             TokenInput::Hnt => BlockchainTokenTypeV1::Hnt.into(),
             TokenInput::Hst => BlockchainTokenTypeV1::Hst.into(),
             TokenInput::Iot => BlockchainTokenTypeV1::Iot.into(),
@@ -126,7 +203,7 @@ async fn ledger_v2(opts: Opts, cmd: Cmd) -> Result<Response<BlockchainTxnPayment
     };
 
     let mut txn = BlockchainTxnPaymentV2 {
-        payer: pubkey.to_vec(),
+        payer: payer.to_vec(),
         payments: vec![payment],
         nonce,
         fee: 0,
@@ -136,34 +213,16 @@ async fn ledger_v2(opts: Opts, cmd: Cmd) -> Result<Response<BlockchainTxnPayment
         fee
     } else {
         txn.txn_fee(
-            &get_txn_fees(&client)
+            &get_txn_fees(payer.network)
                 .await
                 .map_err(|_| Error::getting_fees())?,
         )
         .map_err(|_| Error::getting_fees())?
     };
-
-    print_proposed_txn_v2(&txn)?;
-
-    let adpu_cmd = txn.apdu_serialize(opts.account)?;
-
-    let exchange_pay_tx_result = read_from_ledger(&ledger_transport, adpu_cmd).await?;
-
-    if exchange_pay_tx_result.data.len() == 1 {
-        return Ok(Response::UserDeniedTransaction);
-    }
-
-    let txn = BlockchainTxnPaymentV2::decode(exchange_pay_tx_result.data.as_slice())?;
-    let payer = PublicKey::from_bytes(&txn.payer)?;
-
-    let envelope = txn.in_envelope();
-    // submit the signed tansaction to the API
-    let pending_txn_status = submit_txn(&client, &envelope).await?;
-
-    Ok(Response::Txn(txn, pending_txn_status.hash, payer.network))
+    Ok(txn)
 }
 
-pub fn print_proposed_txn_v2(txn: &BlockchainTxnPaymentV2) -> Result {
+pub fn print_proposed_txn_v2(txn: &BlockchainTxnPaymentV2, price: Option<&Price>) -> Result {
     let payment = &txn.payments[0];
     let payee = PublicKey::try_from(payment.payee.clone())?;
     let token_type = BlockchainTokenTypeV1::from_i32(txn.payments[0].token_type)
@@ -185,20 +244,27 @@ pub fn print_proposed_txn_v2(txn: &BlockchainTxnPaymentV2) -> Result {
 
     let mut table = Table::new();
     println!("Creating the following transaction:");
-    table.add_row(row![
+    let mut titles = row![
         "Payee",
         &format!("Pay Amount {}", units),
         "Nonce",
         "Memo",
         "DC Fee"
-    ]);
-    table.add_row(row![
-        payee,
+    ];
+    let mut values = row![
+        labeled(&payee),
         Token::from(payment.amount),
         txn.nonce,
         Memo::from(payment.memo).to_string(),
         txn.fee
-    ]);
+    ];
+    // the oracle only prices HNT
+    if let (Some(price), BlockchainTokenTypeV1::Hnt) = (price, token_type) {
+        titles.add_cell(cell!("Value USD"));
+        values.add_cell(cell!(price.fmt_usd(Hnt::from(payment.amount).get_decimal())));
+    }
+    table.add_row(titles);
+    table.add_row(values);
     table.printstd();
     println!(
         "WARNING: do not use this output as the source of truth. Instead, rely \
@@ -222,6 +288,28 @@ impl FromStr for TokenInput {
     }
 }
 
+impl fmt::Display for TokenInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TokenInput::Hnt => "hnt",
+            TokenInput::Iot => "iot",
+            TokenInput::Mobile => "mobile",
+            TokenInput::Hst => "hst",
+        })
+    }
+}
+
+impl From<BlockchainTokenTypeV1> for TokenInput {
+    fn from(token_type: BlockchainTokenTypeV1) -> Self {
+        match token_type {
+            BlockchainTokenTypeV1::Hnt => TokenInput::Hnt,
+            BlockchainTokenTypeV1::Hst => TokenInput::Hst,
+            BlockchainTokenTypeV1::Iot => TokenInput::Iot,
+            BlockchainTokenTypeV1::Mobile => TokenInput::Mobile,
+        }
+    }
+}
+
 fn invalid_balance_response<T>(
     token: &TokenInput,
     account: &Account,
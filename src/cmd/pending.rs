@@ -0,0 +1,47 @@
+use super::*;
+use crate::api;
+
+#[derive(Debug, StructOpt)]
+/// List the pending transactions of this Ledger account with their nonces
+/// and status
+pub struct Cmd {}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        let ledger_transport = get_ledger_transport(&opts).await?;
+        let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
+        let address = pubkey.to_string();
+
+        let account = api::account(version.network, &address).await?;
+        let pending = api::pending_txns(version.network, &address).await?;
+
+        println!(
+            "Account {} has chain nonce {} and speculative nonce {}",
+            labeled(&pubkey),
+            account.nonce,
+            account.speculative_nonce
+        );
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(row![
+            "Hash",
+            "Type",
+            "Nonce",
+            "Status",
+            "Created",
+            "Failed Reason"
+        ]);
+        for txn in &pending {
+            table.add_row(row![
+                txn.hash,
+                txn.txn_type,
+                txn.nonce.map(|nonce| nonce.to_string()).unwrap_or_default(),
+                txn.status,
+                txn.created_at,
+                txn.failed_reason,
+            ]);
+        }
+        table.printstd();
+        Ok(None)
+    }
+}
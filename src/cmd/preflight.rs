@@ -0,0 +1,410 @@
+use super::*;
+use crate::{
+    api,
+    cmd::pay::TokenInput,
+    policy::{self, Policy, Spend, SpendKind},
+};
+use helium_proto::BlockchainTokenTypeV1;
+use std::{collections::HashMap, str::FromStr};
+
+/// Chain vars holding lists of b58 encoded keys. Since keys carry their
+/// network, these tell us which network an API is serving.
+const NETWORK_KEY_VARS: [&str; 2] = ["price_oracle_public_keys", "staking_keys"];
+
+/// Transactions that can be checked before they are sent to the Ledger for
+/// signing
+pub trait Preflight {
+    /// Every address referenced by the transaction
+    fn addresses(&self) -> Result<Vec<PublicKey>>;
+    /// Tokens leaving any account as part of the transaction
+    fn spends(&self) -> Result<Vec<Spend>>;
+    /// The payer and nonce for transactions that use the account nonce
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>>;
+    /// The account paying the transaction fee and the fee in DC
+    fn fee(&self) -> Result<(PublicKey, u64)>;
+}
+
+/// Runs all checks for the given transactions against the network reported
+/// by the Ledger app, the API serving it and the spending policy of the
+/// given Ledger account. Any failure aborts the command before the device is
+/// asked to sign.
+pub async fn check<T: Preflight>(
+    version: &Version,
+    opts: &Opts,
+    account: &PublicKey,
+    txns: &[T],
+) -> Result {
+    check_network(version.network, txns)?;
+    check_api_network(version.network).await?;
+    check_pending(version.network, account, txns).await?;
+    check_balance(version.network, account, txns).await?;
+    check_policy(version.network, opts, account, txns)
+}
+
+/// Rejects transactions referencing an address on another network than the
+/// one the Ledger app is running on
+pub fn check_network<T: Preflight>(network: Network, txns: &[T]) -> Result {
+    for txn in txns {
+        for address in txn.addresses()? {
+            if address.network != network {
+                return Err(Error::NetworkMismatch(format!(
+                    "{} is a {} address but the Ledger is on {}",
+                    address, address.network, network
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verifies that the configured API serves the given network by checking the
+/// network of the keys found in its chain vars. The vars of the session are
+/// used, so a pinned --vars snapshot is checked instead of the API.
+pub async fn check_api_network(network: Network) -> Result {
+    let vars = crate::vars::get(network).await?;
+    let keys: Vec<PublicKey> = NETWORK_KEY_VARS
+        .iter()
+        .filter_map(|var| vars.get(*var).and_then(|value| value.as_array()))
+        .flatten()
+        .filter_map(|value| value.as_str())
+        .filter_map(|key| PublicKey::from_str(key).ok())
+        .collect();
+    if keys.is_empty() {
+        println!(
+            "WARNING: unable to verify that {} serves {}",
+            api_url(network),
+            network
+        );
+        return Ok(());
+    }
+    if let Some(key) = keys.iter().find(|key| key.network != network) {
+        return Err(Error::NetworkMismatch(format!(
+            "API at {} serves {} but the Ledger is on {}",
+            api_url(network),
+            key.network,
+            network
+        )));
+    }
+    Ok(())
+}
+
+/// Warns about pending transactions of the given account, about nonces that
+/// duplicate a pending transaction or leave a gap, and about a chain nonce
+/// that disagrees with the speculative nonce. None of these are fatal since
+/// the user may intend to replace or queue behind a stuck transaction.
+pub async fn check_pending<T: Preflight>(
+    network: Network,
+    account: &PublicKey,
+    txns: &[T],
+) -> Result {
+    let address = account.to_string();
+    let pending: Vec<api::PendingTxn> = api::pending_txns(network, &address)
+        .await?
+        .into_iter()
+        .filter(api::PendingTxn::is_pending)
+        .collect();
+    if !pending.is_empty() {
+        println!(
+            "WARNING: {} has {} pending transaction(s). Use the pending command to review them",
+            address,
+            pending.len()
+        );
+    }
+
+    let chain = api::account(network, &address).await?;
+    if chain.nonce != chain.speculative_nonce {
+        println!(
+            "WARNING: chain nonce {} and speculative nonce {} disagree. An earlier \
+            transaction may be stuck",
+            chain.nonce, chain.speculative_nonce
+        );
+    }
+
+    let highest = pending
+        .iter()
+        .filter_map(|txn| txn.nonce)
+        .chain(std::iter::once(chain.speculative_nonce))
+        .max()
+        .unwrap_or(chain.speculative_nonce);
+    for txn in txns {
+        let nonce = match txn.nonce()? {
+            Some((payer, nonce)) if &payer == account => nonce,
+            _ => continue,
+        };
+        if let Some(duplicate) = pending.iter().find(|txn| txn.nonce == Some(nonce)) {
+            println!(
+                "WARNING: nonce {} duplicates pending transaction {}",
+                nonce, duplicate.hash
+            );
+        } else if nonce <= chain.nonce {
+            println!(
+                "WARNING: nonce {} has already been used on chain (chain nonce {})",
+                nonce, chain.nonce
+            );
+        } else if nonce > highest + 1 {
+            println!(
+                "WARNING: nonce {} skips ahead of the next expected nonce {}. The \
+                transaction will not clear until the gap is filled",
+                nonce,
+                highest + 1
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Rejects transactions the given account cannot fund. Fees are paid from
+/// the DC balance while it lasts; fees it cannot cover are implicitly burned
+/// from HNT at the oracle price, so they are added to the HNT needed.
+pub async fn check_balance<T: Preflight>(
+    network: Network,
+    account: &PublicKey,
+    txns: &[T],
+) -> Result {
+    let chain = api::account(network, &account.to_string()).await?;
+
+    let mut needed: HashMap<TokenInput, u64> = HashMap::new();
+    let mut dc_balance = chain.dc_balance;
+    let mut burned_fees = 0;
+    for txn in txns {
+        for spend in txn.spends()? {
+            if &spend.payer == account {
+                *needed.entry(spend.token).or_default() += spend.amount;
+            }
+        }
+        let (payer, fee) = txn.fee()?;
+        if &payer != account {
+            continue;
+        }
+        // the chain burns HNT for the whole fee of a transaction the DC
+        // balance cannot cover
+        if dc_balance >= fee {
+            dc_balance -= fee;
+        } else {
+            burned_fees += fee;
+        }
+    }
+
+    let mut fee_burn = 0;
+    if burned_fees > 0 {
+        let price = Price::current(network).await?;
+        fee_burn = u64::from(price.hnt_for_dc(burned_fees)?);
+        println!(
+            "WARNING: {} DC of fees are not covered by the DC balance and will be paid by \
+            burning about {} HNT",
+            burned_fees,
+            Hnt::from(fee_burn)
+        );
+    }
+    *needed.entry(TokenInput::Hnt).or_default() += fee_burn;
+
+    for (token, amount) in needed {
+        let balance = match token {
+            TokenInput::Hnt => u64::from(chain.balance),
+            TokenInput::Hst => u64::from(chain.sec_balance),
+            TokenInput::Iot => u64::from(chain.iot_balance),
+            TokenInput::Mobile => u64::from(chain.mobile_balance),
+        };
+        if amount > balance {
+            let detail = if token == TokenInput::Hnt && fee_burn > 0 {
+                format!(" including {} burned for fees", Hnt::from(fee_burn))
+            } else {
+                String::new()
+            };
+            return Err(Error::Balance(format!(
+                "{} needs {} {}{} but only has {}",
+                account,
+                Token::from(amount),
+                token,
+                detail,
+                Token::from(balance)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the spends of the given account against the local spending policy,
+/// if there is one. Violations are only let through with --override-policy,
+/// in which case they are logged.
+pub fn check_policy<T: Preflight>(
+    network: Network,
+    opts: &Opts,
+    account: &PublicKey,
+    txns: &[T],
+) -> Result {
+    let policy = match Policy::load(network)? {
+        Some(policy) => policy,
+        None => return Ok(()),
+    };
+    let mut spends = Vec::new();
+    for txn in txns {
+        spends.extend(txn.spends()?);
+    }
+    let violations = policy.violations(account, &spends)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    for violation in &violations {
+        println!("POLICY: {}", violation);
+    }
+    if opts.override_policy {
+        policy::log_override(account, &violations)?;
+        println!("WARNING: spending policy overridden. This override has been logged.");
+        Ok(())
+    } else {
+        Err(Error::Policy(format!(
+            "{} rule(s) broken. Use --override-policy to proceed anyway",
+            violations.len()
+        )))
+    }
+}
+
+impl Preflight for BlockchainTxnPaymentV2 {
+    fn addresses(&self) -> Result<Vec<PublicKey>> {
+        let mut addresses = vec![PublicKey::from_bytes(&self.payer)?];
+        for payment in &self.payments {
+            addresses.push(PublicKey::from_bytes(&payment.payee)?);
+        }
+        Ok(addresses)
+    }
+
+    fn spends(&self) -> Result<Vec<Spend>> {
+        let payer = PublicKey::from_bytes(&self.payer)?;
+        self.payments
+            .iter()
+            .map(|payment| -> Result<Spend> {
+                let token_type =
+                    BlockchainTokenTypeV1::from_i32(payment.token_type).ok_or_else(Error::txn)?;
+                Ok(Spend {
+                    kind: SpendKind::Payment,
+                    payer: payer.clone(),
+                    payee: PublicKey::from_bytes(&payment.payee)?,
+                    token: TokenInput::from(token_type),
+                    amount: payment.amount,
+                    memo: Some(payment.memo),
+                })
+            })
+            .collect()
+    }
+
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>> {
+        Ok(Some((PublicKey::from_bytes(&self.payer)?, self.nonce)))
+    }
+
+    fn fee(&self) -> Result<(PublicKey, u64)> {
+        Ok((PublicKey::from_bytes(&self.payer)?, self.fee))
+    }
+}
+
+impl Preflight for BlockchainTxnTokenBurnV1 {
+    fn addresses(&self) -> Result<Vec<PublicKey>> {
+        Ok(vec![
+            PublicKey::from_bytes(&self.payer)?,
+            PublicKey::from_bytes(&self.payee)?,
+        ])
+    }
+
+    fn spends(&self) -> Result<Vec<Spend>> {
+        Ok(vec![Spend {
+            kind: SpendKind::Burn,
+            payer: PublicKey::from_bytes(&self.payer)?,
+            payee: PublicKey::from_bytes(&self.payee)?,
+            token: TokenInput::Hnt,
+            amount: self.amount,
+            memo: Some(self.memo),
+        }])
+    }
+
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>> {
+        Ok(Some((PublicKey::from_bytes(&self.payer)?, self.nonce)))
+    }
+
+    fn fee(&self) -> Result<(PublicKey, u64)> {
+        Ok((PublicKey::from_bytes(&self.payer)?, self.fee))
+    }
+}
+
+impl Preflight for BlockchainTxnStakeValidatorV1 {
+    fn addresses(&self) -> Result<Vec<PublicKey>> {
+        Ok(vec![
+            PublicKey::from_bytes(&self.owner)?,
+            PublicKey::from_bytes(&self.address)?,
+        ])
+    }
+
+    fn spends(&self) -> Result<Vec<Spend>> {
+        Ok(vec![Spend {
+            kind: SpendKind::Stake,
+            payer: PublicKey::from_bytes(&self.owner)?,
+            payee: PublicKey::from_bytes(&self.address)?,
+            token: TokenInput::Hnt,
+            amount: self.stake,
+            memo: None,
+        }])
+    }
+
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>> {
+        Ok(None)
+    }
+
+    fn fee(&self) -> Result<(PublicKey, u64)> {
+        Ok((PublicKey::from_bytes(&self.owner)?, self.fee))
+    }
+}
+
+impl Preflight for BlockchainTxnUnstakeValidatorV1 {
+    fn addresses(&self) -> Result<Vec<PublicKey>> {
+        Ok(vec![
+            PublicKey::from_bytes(&self.owner)?,
+            PublicKey::from_bytes(&self.address)?,
+        ])
+    }
+
+    // the stake returns to the owner so nothing is spent
+    fn spends(&self) -> Result<Vec<Spend>> {
+        Ok(vec![])
+    }
+
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>> {
+        Ok(None)
+    }
+
+    fn fee(&self) -> Result<(PublicKey, u64)> {
+        Ok((PublicKey::from_bytes(&self.owner)?, self.fee))
+    }
+}
+
+impl Preflight for BlockchainTxnTransferValidatorStakeV1 {
+    fn addresses(&self) -> Result<Vec<PublicKey>> {
+        Ok(vec![
+            PublicKey::from_bytes(&self.old_owner)?,
+            PublicKey::from_bytes(&self.new_owner)?,
+            PublicKey::from_bytes(&self.old_address)?,
+            PublicKey::from_bytes(&self.new_address)?,
+        ])
+    }
+
+    // the new owner pays the old owner for the stake, if anything
+    fn spends(&self) -> Result<Vec<Spend>> {
+        if self.payment_amount == 0 {
+            return Ok(vec![]);
+        }
+        Ok(vec![Spend {
+            kind: SpendKind::StakeTransfer,
+            payer: PublicKey::from_bytes(&self.new_owner)?,
+            payee: PublicKey::from_bytes(&self.old_owner)?,
+            token: TokenInput::Hnt,
+            amount: self.payment_amount,
+            memo: None,
+        }])
+    }
+
+    fn nonce(&self) -> Result<Option<(PublicKey, u64)>> {
+        Ok(None)
+    }
+
+    fn fee(&self) -> Result<(PublicKey, u64)> {
+        Ok((PublicKey::from_bytes(&self.old_owner)?, self.fee))
+    }
+}
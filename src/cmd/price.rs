@@ -0,0 +1,40 @@
+use super::*;
+
+#[derive(Debug, StructOpt)]
+/// Show the current HNT price reported by the chain's price oracles, and the
+/// amount of Data Credits one HNT burns to at that price.
+pub struct Cmd {
+    /// Show the testnet oracle price instead of mainnet
+    #[structopt(long)]
+    testnet: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts) -> Result<Option<(String, Network)>> {
+        let network = if self.testnet {
+            Network::TestNet
+        } else {
+            Network::MainNet
+        };
+        // a user supplied price is shown as is, which is handy to check the
+        // DC conversion of a price
+        let price = match opts.price {
+            Some(usd) => Price { usd, block: None },
+            None => Price::current(network).await?,
+        };
+
+        let mut table = Table::new();
+        table.add_row(row!["Network", "USD per HNT", "DC per HNT", "Oracle Block"]);
+        table.add_row(row![
+            network,
+            price.usd,
+            price.dc_for_hnt(rust_decimal::Decimal::ONE),
+            price
+                .block
+                .map(|block| block.to_string())
+                .unwrap_or_else(|| "user supplied".to_string()),
+        ]);
+        table.printstd();
+        Ok(None)
+    }
+}
@@ -0,0 +1,82 @@
+use super::*;
+use crate::{cmd::pay::TokenInput, memo::Memo, payment_request::PaymentRequest};
+use rust_decimal::Decimal;
+
+#[derive(Debug, StructOpt)]
+/// Show the address of the selected Ledger account to receive tokens on, and
+/// display it on the Ledger for confirmation. Share the address only once it
+/// matches the Ledger display.
+pub struct Cmd {
+    /// Address the account is expected to have, or an @label from the address
+    /// book. Fails if the Ledger derives a different address
+    #[structopt(long)]
+    expect: Option<Address>,
+
+    /// Amount to request. Shows a payment request URI instead of the bare
+    /// address, as do --token and --memo
+    #[structopt(long)]
+    amount: Option<Token>,
+
+    /// Type of token to request (hnt, iot, mobile, hst). Defaults to hnt
+    #[structopt(long)]
+    token: Option<TokenInput>,
+
+    /// Memo to request. Provide as a base64 encoded string
+    #[structopt(long)]
+    memo: Option<Memo>,
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts, _version: Version) -> Result<Option<(String, Network)>> {
+        // refused like the URI parser does, so the request can be paid
+        if let Some(amount) = &self.amount {
+            if amount.get_decimal() <= Decimal::ZERO {
+                return Err(Error::PaymentRequest(format!(
+                    "amount {} is not positive",
+                    amount
+                )));
+            }
+        }
+
+        let ledger_transport = get_ledger_transport(&opts).await?;
+        let pubkey = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
+
+        if let Some(expect) = &self.expect {
+            let expect = expect.resolve(pubkey.network)?;
+            if expect != pubkey {
+                return Err(Error::AddressMismatch(format!(
+                    "account {} of this Ledger is {} but {} was expected",
+                    opts.account, pubkey, expect
+                )));
+            }
+        }
+
+        let mut table = Table::new();
+        table.add_row(row!["Account", "Network", "Address"]);
+        table.add_row(row![opts.account, pubkey.network, labeled(&pubkey)]);
+        table.printstd();
+
+        if self.amount.is_some() || self.token.is_some() || self.memo.is_some() {
+            let request = PaymentRequest {
+                address: pubkey.clone(),
+                amount: self.amount,
+                token: self.token.unwrap_or(TokenInput::Hnt),
+                memo: self.memo,
+            };
+            println!("Payment request: {}", request);
+            print_qr(&request.to_string())?;
+        } else {
+            print_qr(&pubkey.to_string())?;
+        }
+
+        println!("Confirm that the Ledger displays the same address");
+        let displayed = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::On).await?;
+        if displayed != pubkey {
+            return Err(Error::AddressMismatch(format!(
+                "the Ledger displayed {} but {} was derived before",
+                displayed, pubkey
+            )));
+        }
+        Ok(None)
+    }
+}
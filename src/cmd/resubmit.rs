@@ -0,0 +1,90 @@
+use super::*;
+use crate::{
+    api::{self, TxnStatus},
+    journal::{self as store, Entry, Status},
+    partial,
+};
+use std::{fs, path::Path};
+
+#[derive(Debug, StructOpt)]
+/// Resubmit a signed transaction from the journal or from a file. The chain
+/// and the pending transactions of the API are checked first so that a
+/// transaction is never submitted twice.
+pub struct Cmd {
+    /// Hash of a journaled transaction, or a file holding a base64 encoded
+    /// signed transaction or a partially signed transaction container
+    #[structopt(name = "HASH|FILE")]
+    txn: String,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let envelope = self.read_envelope()?;
+        let txn = store::from_envelope(&envelope)?;
+        let hash = txn.hash()?;
+        // the payer or owner comes first and is on the transaction's network
+        let network = txn.addresses()?[0].network;
+
+        match api::txn_status(network, &hash).await? {
+            TxnStatus::Confirmed(height) => {
+                println!(
+                    "Transaction {} is already confirmed at block {}",
+                    hash, height
+                );
+                return Ok(None);
+            }
+            TxnStatus::Pending => {
+                println!("Transaction {} is already pending", hash);
+                return Ok(None);
+            }
+            TxnStatus::Failed(reason) => {
+                println!(
+                    "WARNING: a previous submission failed ({}). Resubmitting",
+                    reason
+                );
+            }
+            TxnStatus::Unknown => (),
+        }
+
+        // a transaction read from a file was signed elsewhere, so it is
+        // journaled without a Ledger account of this device
+        let entry = match store::entries()?
+            .into_iter()
+            .rev()
+            .find(|entry| entry.hash == hash)
+        {
+            Some(entry) => entry,
+            None => Entry::new(None, None, network, txn.as_ref(), Status::Signed, None)?,
+        };
+
+        let result = submit_txn_with_retry(network, &txn.envelope()).await;
+        let entry = match &result {
+            Ok(status) => entry.with_status(Status::Submitted, Some(status.hash.clone())),
+            Err(err) => entry.with_status(Status::Failed, Some(err.to_string())),
+        };
+        // the outcome of the resubmission matters more than the journal,
+        // which must not turn an accepted transaction into an error
+        if let Err(err) = store::append(&entry) {
+            println!("WARNING: unable to journal the resubmission: {}", err);
+        }
+        Ok(Some((result?.hash, network)))
+    }
+
+    fn read_envelope(&self) -> Result<BlockchainTxn> {
+        if Path::new(&self.txn).is_file() {
+            let contents = fs::read_to_string(&self.txn)?;
+            return Ok(partial::parse(&contents)?.0);
+        }
+        store::entries()?
+            .into_iter()
+            .rev()
+            .find(|entry| entry.hash == self.txn)
+            .ok_or_else(|| {
+                Error::Resubmit(format!(
+                    "{} is neither a journaled transaction hash nor a file",
+                    self.txn
+                ))
+            })?
+            .envelope()
+    }
+}
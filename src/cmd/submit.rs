@@ -0,0 +1,51 @@
+use super::*;
+use crate::{
+    journal::{self as store, Entry, Status},
+    partial,
+};
+
+#[derive(Debug, StructOpt)]
+/// Submit a transaction once every party has signed it, from a partially
+/// signed transaction container or a base64 encoded transaction. Containers
+/// are verified before submission.
+pub struct Cmd {
+    /// File to submit, - for stdin, or a base64 encoded transaction
+    #[structopt(name = "FILE|TRANSACTION")]
+    input: String,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        let (envelope, _container) = partial::parse(&read_input(&self.input)?)?;
+        let txn = store::from_envelope(&envelope)?;
+
+        let unsigned: Vec<&str> = txn
+            .signers()?
+            .iter()
+            .filter(|signer| !signer.has_signed())
+            .map(|signer| signer.role)
+            .collect();
+        if !unsigned.is_empty() {
+            return Err(Error::Container(format!(
+                "not signed by {}",
+                unsigned.join(", ")
+            )));
+        }
+        // the payer or owner comes first and is on the transaction's network
+        let network = txn.addresses()?[0].network;
+
+        // signed elsewhere, so no Ledger account of this device is recorded
+        let entry = Entry::new(None, None, network, txn.as_ref(), Status::Signed, None)?;
+        let result = submit_txn_with_retry(network, &envelope).await;
+        let entry = match &result {
+            Ok(status) => entry.with_status(Status::Submitted, Some(status.hash.clone())),
+            Err(err) => entry.with_status(Status::Failed, Some(err.to_string())),
+        };
+        // the outcome of the submission matters more than the journal, which
+        // must not turn an accepted transaction into an error
+        if let Err(err) = store::append(&entry) {
+            println!("WARNING: unable to journal the submission: {}", err);
+        }
+        Ok(Some((result?.hash, network)))
+    }
+}
@@ -0,0 +1,157 @@
+//! Chain state checks run before validator transactions are signed
+
+use crate::{api, cmd::*};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// What the API knows about a validator
+pub struct ValidatorState {
+    pub owner: String,
+    pub stake: u64,
+    pub stake_status: String,
+}
+
+impl ValidatorState {
+    pub fn is_staked(&self) -> bool {
+        self.stake_status == "staked"
+    }
+
+    pub fn is_unstaked(&self) -> bool {
+        self.stake_status == "unstaked"
+    }
+}
+
+/// Looks up a validator. Returns None for validators the chain has never
+/// seen, which is the case for validators that were never staked.
+pub async fn validator_state(
+    network: Network,
+    address: &PublicKey,
+) -> Result<Option<ValidatorState>> {
+    match api::validator(network, &address.to_string()).await {
+        Ok(validator) => Ok(Some(ValidatorState {
+            owner: validator.owner,
+            stake: u64::from(validator.stake),
+            stake_status: validator.stake_status.to_string(),
+        })),
+        Err(err) if err.is_not_found() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+async fn minimum_stake(network: Network) -> Result<u64> {
+    crate::vars::get(network)
+        .await?
+        .get("validator_minimum_stake")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| Error::Validator("validator_minimum_stake chain var not found".to_string()))
+}
+
+/// Checks that every validator in a batch of stakes appears only once, is
+/// not staked yet and is staked with at least the minimum stake
+pub async fn check_stake(network: Network, txns: &[BlockchainTxnStakeValidatorV1]) -> Result {
+    let minimum = minimum_stake(network).await?;
+    let mut seen = HashSet::new();
+    for txn in txns {
+        let address = PublicKey::from_bytes(&txn.address)?;
+        if !seen.insert(txn.address.clone()) {
+            return Err(Error::Validator(format!(
+                "{} appears more than once in the batch",
+                address
+            )));
+        }
+        if txn.stake < minimum {
+            return Err(Error::Validator(format!(
+                "stake of {} for {} is below the validator_minimum_stake of {}",
+                Hnt::from(txn.stake),
+                address,
+                Hnt::from(minimum)
+            )));
+        }
+        if txn.stake > minimum {
+            println!(
+                "WARNING: stake of {} for {} is above the validator_minimum_stake of {}",
+                Hnt::from(txn.stake),
+                address,
+                Hnt::from(minimum)
+            );
+        }
+        if let Some(state) = validator_state(network, &address).await? {
+            if !state.is_unstaked() {
+                return Err(Error::Validator(format!(
+                    "{} is {} with owner {}",
+                    address, state.stake_status, state.owner
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the validator of an unstake is staked, owned by the unstaking
+/// account and that the stake amount matches its actual stake
+pub async fn check_unstake(network: Network, txn: &BlockchainTxnUnstakeValidatorV1) -> Result {
+    let address = PublicKey::from_bytes(&txn.address)?;
+    let owner = PublicKey::from_bytes(&txn.owner)?;
+    let state = staked_by(network, &address, &owner).await?;
+    check_stake_amount(&address, txn.stake_amount, &state)
+}
+
+/// Checks that the old validator of a transfer is staked and owned by the
+/// old owner, that the stake amount matches its actual stake and that a new
+/// validator is not staked yet
+pub async fn check_transfer(
+    network: Network,
+    txn: &BlockchainTxnTransferValidatorStakeV1,
+) -> Result {
+    let old_address = PublicKey::from_bytes(&txn.old_address)?;
+    let new_address = PublicKey::from_bytes(&txn.new_address)?;
+    let old_owner = PublicKey::from_bytes(&txn.old_owner)?;
+    let state = staked_by(network, &old_address, &old_owner).await?;
+    check_stake_amount(&old_address, txn.stake_amount, &state)?;
+    if new_address != old_address {
+        if let Some(new_state) = validator_state(network, &new_address).await? {
+            if !new_state.is_unstaked() {
+                return Err(Error::Validator(format!(
+                    "new validator {} is {} with owner {}",
+                    new_address, new_state.stake_status, new_state.owner
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn staked_by(
+    network: Network,
+    address: &PublicKey,
+    owner: &PublicKey,
+) -> Result<ValidatorState> {
+    let state = validator_state(network, address)
+        .await?
+        .ok_or_else(|| Error::Validator(format!("{} is not a known validator", address)))?;
+    if state.owner != owner.to_string() {
+        return Err(Error::Validator(format!(
+            "{} is owned by {}, not {}",
+            address, state.owner, owner
+        )));
+    }
+    if !state.is_staked() {
+        return Err(Error::Validator(format!(
+            "{} is {}, not staked",
+            address, state.stake_status
+        )));
+    }
+    Ok(state)
+}
+
+fn check_stake_amount(address: &PublicKey, stake_amount: u64, state: &ValidatorState) -> Result {
+    if stake_amount != state.stake {
+        return Err(Error::Validator(format!(
+            "stake amount {} does not match the actual stake of {} for {}",
+            Hnt::from(stake_amount),
+            Hnt::from(state.stake),
+            address
+        )));
+    }
+    Ok(())
+}
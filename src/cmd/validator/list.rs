@@ -0,0 +1,195 @@
+use crate::{api, cmd::*};
+use chrono::{Duration, Utc};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::{num::NonZeroU8, path::PathBuf};
+
+#[derive(Debug, StructOpt)]
+/// List the validators owned by the selected Ledger account, or by every
+/// account found with --scan, with their stake, status and recent rewards.
+pub struct Cmd {
+    /// Discover accounts as `balance --scan` does and list the validators of
+    /// all of them
+    #[structopt(long)]
+    scan: bool,
+    /// Number of consecutive accounts without activity after which a scan
+    /// stops. Must be at least 1
+    #[structopt(long, default_value = "3")]
+    gap_limit: NonZeroU8,
+    /// Maximum number of concurrent API lookups during a scan and while
+    /// fetching validator details
+    #[structopt(long, default_value = "4")]
+    parallel: u8,
+    /// Number of days to sum validator rewards over
+    #[structopt(long, default_value = "30")]
+    rewards_days: u32,
+    /// Export format (json or csv) instead of a table
+    #[structopt(long)]
+    format: Option<ExportFormat>,
+    /// File to write the export to. Standard output is used if not given.
+    /// Requires --format
+    #[structopt(long, requires = "format")]
+    output: Option<PathBuf>,
+}
+
+/// A validator and its state, as listed and exported
+#[derive(Debug, Serialize)]
+struct Record {
+    account: u8,
+    owner: String,
+    address: String,
+    name: String,
+    stake: String,
+    stake_status: String,
+    release_height: Option<u64>,
+    blocks_remaining: Option<u64>,
+    penalty: f64,
+    version: Option<u64>,
+    rewards: String,
+}
+
+impl Cmd {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        let network = version.network;
+        let ledger_transport = get_ledger_transport(&opts).await?;
+        let owners: Vec<(u8, PublicKey)> = if self.scan {
+            balance::discover(&ledger_transport, network, self.gap_limit, self.parallel)
+                .await?
+                .into_iter()
+                .map(|(index, pubkey, _)| (index, pubkey))
+                .collect()
+        } else {
+            vec![(
+                opts.account,
+                get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?,
+            )]
+        };
+
+        let height = api::height(network).await?;
+        let min_time = Utc::now()
+            .checked_sub_signed(Duration::days(i64::from(self.rewards_days)))
+            .ok_or_else(|| Error::Validator("--rewards-days is out of range".to_string()))?
+            .to_rfc3339();
+        let mut validators = Vec::new();
+        for (index, owner) in owners {
+            for validator in
+                api::get_all(network, &format!("/accounts/{}/validators", owner), &[]).await?
+            {
+                validators.push((index, validator));
+            }
+        }
+        let min_time = &min_time;
+        let records = stream::iter(validators)
+            .map(|(index, validator)| async move {
+                record(network, index, &validator, height, min_time).await
+            })
+            .buffered(self.parallel.max(1) as usize)
+            .collect::<Vec<Result<Record>>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<Record>>>()?;
+
+        match self.format {
+            None => print_records(&records, self.rewards_days),
+            Some(format) => write_export(format, self.output.as_deref(), &records)?,
+        }
+        Ok(None)
+    }
+}
+
+fn str_field(value: &Value, field: &str) -> String {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string()
+}
+
+async fn record(
+    network: Network,
+    account: u8,
+    validator: &Value,
+    height: u64,
+    min_time: &str,
+) -> Result<Record> {
+    let address = str_field(validator, "address");
+    let stake_status = str_field(validator, "stake_status");
+
+    // the release height is only known from the unstake transaction
+    let release_height = if stake_status == "cooldown" {
+        api::get_all(
+            network,
+            &format!("/validators/{}/activity", address),
+            &[("filter_types", "unstake_validator_v1")],
+        )
+        .await?
+        .first()
+        .and_then(|txn| txn.get("stake_release_height"))
+        .and_then(Value::as_u64)
+    } else {
+        None
+    };
+
+    let rewards: Option<Value> = api::get(
+        network,
+        &format!("/validators/{}/rewards/sum", address),
+        &[("min_time", min_time)],
+    )
+    .await?;
+    let rewards = rewards
+        .as_ref()
+        .and_then(|rewards| rewards.get("sum"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+
+    Ok(Record {
+        account,
+        owner: str_field(validator, "owner"),
+        name: str_field(validator, "name"),
+        stake: Hnt::from(validator.get("stake").and_then(Value::as_u64).unwrap_or(0)).to_string(),
+        stake_status,
+        release_height,
+        blocks_remaining: release_height.map(|release| release.saturating_sub(height)),
+        penalty: validator
+            .get("penalty")
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0),
+        version: validator.get("version_heartbeat").and_then(Value::as_u64),
+        rewards: Hnt::from(rewards).to_string(),
+        address,
+    })
+}
+
+fn print_records(records: &[Record], rewards_days: u32) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(row![
+        "Account",
+        "Validator",
+        "Name",
+        "Stake",
+        "Status",
+        "Release Height",
+        "Blocks Remaining",
+        "Penalty",
+        "Version",
+        &format!("Rewards {}d", rewards_days),
+    ]);
+    let optional = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+    for record in records {
+        table.add_row(row![
+            record.account,
+            record.address,
+            record.name,
+            record.stake,
+            record.stake_status,
+            optional(record.release_height),
+            optional(record.blocks_remaining),
+            format!("{:.2}", record.penalty),
+            optional(record.version),
+            record.rewards,
+        ]);
+    }
+    table.printstd();
+}
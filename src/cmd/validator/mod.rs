@@ -1,8 +1,11 @@
 use crate::{Network, Opts, Result, StructOpt, Version};
 
-mod stake;
-mod transfer;
-mod unstake;
+mod checks;
+mod list;
+mod progress;
+pub(crate) mod stake;
+pub(crate) mod transfer;
+pub(crate) mod unstake;
 
 #[derive(Debug, StructOpt)]
 /// Commands for validators
@@ -13,6 +16,8 @@ pub enum Cmd {
     Transfer(Box<transfer::Cmd>),
     /// Unstake a validator with the given wallet as the owner.
     Unstake(unstake::Cmd),
+    /// List validators owned by the given wallet(s).
+    List(list::Cmd),
 }
 
 impl Cmd {
@@ -24,6 +29,7 @@ impl Cmd {
             Cmd::Stake(stake) => stake.run(opts, version).await,
             Cmd::Transfer(transfer) => transfer.run(opts, version).await,
             Cmd::Unstake(unstake) => unstake.run(opts, version).await,
+            Cmd::List(list) => list.run(opts, version).await,
         }
     }
 }
@@ -0,0 +1,92 @@
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Progress of a multi validator file, kept next to the input file as
+/// `<input>.progress.json` so that an interrupted run can be resumed
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Progress per validator address
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Submitted to the API
+    Submitted,
+    /// Found to be done on chain already, so nothing was submitted
+    Skipped,
+    /// Denied on the Ledger or failed to submit
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub status: Status,
+    pub updated: DateTime<Utc>,
+    pub hash: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Progress {
+    /// Loads the progress of the given input file, or starts empty if there
+    /// is none yet
+    pub fn load(input: &Path) -> Result<Self> {
+        let path = PathBuf::from(format!("{}.progress.json", input.display()));
+        let mut progress = if path.exists() {
+            serde_json::from_reader::<_, Self>(fs::File::open(&path)?).map_err(|err| {
+                Error::Validator(format!("invalid progress file {}: {}", path.display(), err))
+            })?
+        } else {
+            Self::default()
+        };
+        progress.path = path;
+        Ok(progress)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The progress of a validator, if it was processed in an earlier run
+    pub fn get(&self, address: &str) -> Option<&Entry> {
+        self.entries.get(address)
+    }
+
+    /// Whether a validator needs no more work
+    pub fn is_done(&self, address: &str) -> bool {
+        matches!(
+            self.get(address).map(|entry| entry.status),
+            Some(Status::Submitted) | Some(Status::Skipped)
+        )
+    }
+
+    /// Records the status of a validator and saves the progress right away
+    pub fn record(
+        &mut self,
+        address: &str,
+        status: Status,
+        hash: Option<String>,
+        error: Option<String>,
+    ) -> Result {
+        self.entries.insert(
+            address.to_string(),
+            Entry {
+                status,
+                updated: Utc::now(),
+                hash,
+                error,
+            },
+        );
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
+use super::progress::{Progress, Status};
 use crate::cmd::*;
 use rust_decimal::Decimal;
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, StructOpt)]
 /// Onboard one (or more) validators  with this wallet.
@@ -10,7 +11,10 @@ use std::path::PathBuf;
 /// given.
 ///
 /// Note that multiple staking transactions are submitted individually and not as a
-/// single transaction. Any failures will abort the remaining staking entries.
+/// single transaction. A failure stops the remaining staking entries, but the
+/// progress of a multi stake is recorded next to its input file, and a rerun
+/// continues from the failed entry, skipping entries that were already
+/// submitted or are staked on chain.
 pub enum Cmd {
     /// Stake a single validator
     One(Validator),
@@ -32,6 +36,8 @@ pub enum Cmd {
 ///         "stake": 10000
 ///     }
 /// ]
+///
+/// Addresses may also be given as an @label from the address book.
 pub struct Multi {
     /// File to read multiple stakes from
     path: PathBuf,
@@ -44,8 +50,8 @@ pub enum Response {
 }
 
 impl Cmd {
-    pub async fn run(self, opts: Opts, _version: Version) -> Result<Option<(String, Network)>> {
-        match self.ledger(opts).await? {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        match self.ledger(opts, &version).await? {
             Response::Success => Ok(None),
             Response::InsufficientBalance(balance, send_request) => {
                 println!(
@@ -64,29 +70,52 @@ impl Cmd {
     fn collect_validators(&self) -> Result<Vec<Validator>> {
         match &self {
             Self::One(validator) => Ok(vec![validator.clone()]),
-            Self::Multi(multi) => {
-                let file = std::fs::File::open(multi.path.clone())?;
-                let validators: Vec<Validator> = serde_json::from_reader(file)?;
-                Ok(validators)
-            }
+            Self::Multi(multi) => read_validators(&multi.path),
         }
     }
 
-    pub async fn ledger(self, opts: Opts) -> Result<Response> {
+    pub async fn ledger(self, opts: Opts, version: &Version) -> Result<Response> {
         let validators = self.collect_validators()?;
+        let mut progress = match &self {
+            Self::One(_) => None,
+            Self::Multi(multi) => Some(Progress::load(&multi.path)?),
+        };
 
         let ledger_transport = get_ledger_transport(&opts).await?;
 
         // get account from API so we can get nonce and balance
         let owner = get_pubkey(opts.account, &ledger_transport, PubkeyDisplay::Off).await?;
 
-        let client = new_client(owner.network);
+        // skip entries a previous run got through, or that are staked already
+        let mut stakes = Vec::with_capacity(validators.len());
+        for validator in validators {
+            let address = validator.address.resolve(owner.network)?;
+            if let Some(progress) = progress.as_mut() {
+                let key = address.to_string();
+                if progress.is_done(&key) {
+                    println!("Skipping {}: done in an earlier run", labeled(&address));
+                    continue;
+                }
+                let state = super::checks::validator_state(owner.network, &address).await?;
+                if matches!(state, Some(state) if state.is_staked() && state.owner == owner.to_string())
+                {
+                    println!("Skipping {}: already staked", labeled(&address));
+                    progress.record(&key, Status::Skipped, None, None)?;
+                    continue;
+                }
+            }
+            stakes.push((address, validator.stake));
+        }
+        if stakes.is_empty() {
+            println!("Nothing left to stake");
+            return Ok(Response::Success);
+        }
 
-        let account = accounts::get(&client, &owner.to_string()).await?;
+        let account = api::account(owner.network, &owner.to_string()).await?;
 
-        let total_stake_amount = validators
+        let total_stake_amount = stakes
             .iter()
-            .map(|v| v.stake.get_decimal())
+            .map(|(_, stake)| stake.get_decimal())
             .sum::<Decimal>();
 
         if account.balance.get_decimal() < total_stake_amount {
@@ -96,43 +125,85 @@ impl Cmd {
             ));
         }
 
-        for validator in validators {
-            let mut txn = BlockchainTxnStakeValidatorV1 {
-                owner: owner.to_vec(),
-                address: validator.address.to_vec(),
-                stake: u64::from(validator.stake),
-                fee: 0,
-                owner_signature: vec![],
-            };
-            txn.fee = txn
-                .txn_fee(
-                    &get_txn_fees(&client)
-                        .await
-                        .map_err(|_| Error::getting_fees())?,
-                )
-                .map_err(|_| Error::getting_fees())?;
-            print_proposed_transaction(&txn)?;
+        // build all transactions up front so that the whole batch is checked
+        // before anything is signed
+        let mut txns = Vec::with_capacity(stakes.len());
+        for (address, stake) in stakes {
+            txns.push(build_txn(&owner, &address, stake).await?);
+        }
+        super::checks::check_stake(owner.network, &txns).await?;
+        preflight::check(version, &opts, &owner, &txns).await?;
+        let price = Price::for_opts(&opts, owner.network).await?;
+
+        for txn in txns {
+            let address = PublicKey::from_bytes(&txn.address)?.to_string();
+            print_proposed_transaction(&txn, price.as_ref())?;
 
             let cmd = txn.apdu_serialize(opts.account)?;
             let exchange_pay_tx_result = read_from_ledger(&ledger_transport, cmd).await?;
 
             if exchange_pay_tx_result.data.len() == 1 {
+                if let Some(progress) = progress.as_mut() {
+                    let error = "denied on the Ledger".to_string();
+                    progress.record(&address, Status::Failed, None, Some(error))?;
+                }
                 return Ok(Response::UserDeniedTransaction);
             }
 
             let txn =
                 BlockchainTxnStakeValidatorV1::decode(exchange_pay_tx_result.data.as_slice())?;
-            let envelope = txn.in_envelope();
             // submit the signed tansaction to the API
-            let pending_txn_status = submit_txn(&client, &envelope).await?;
-
-            print_txn(pending_txn_status.hash, owner.network)
+            match submit_signed(&opts, version, &txn).await {
+                Ok(pending_txn_status) => {
+                    if let Some(progress) = progress.as_mut() {
+                        let hash = Some(pending_txn_status.hash.clone());
+                        progress.record(&address, Status::Submitted, hash, None)?;
+                    }
+                    print_txn(pending_txn_status.hash, owner.network)
+                }
+                Err(err) => {
+                    if let Some(progress) = progress.as_mut() {
+                        progress.record(&address, Status::Failed, None, Some(err.to_string()))?;
+                        println!(
+                            "Progress saved to {}. Rerun to continue from this entry",
+                            progress.path().display()
+                        );
+                    }
+                    return Err(err);
+                }
+            }
         }
         Ok(Response::Success)
     }
 }
 
-fn print_proposed_transaction(stake: &BlockchainTxnStakeValidatorV1) -> Result {
+/// Builds the unsigned stake of a validator by the given owner
+pub(crate) async fn build_txn(
+    owner: &PublicKey,
+    address: &PublicKey,
+    stake: Hnt,
+) -> Result<BlockchainTxnStakeValidatorV1> {
+    let mut txn = BlockchainTxnStakeValidatorV1 {
+        owner: owner.to_vec(),
+        address: address.to_vec(),
+        stake: u64::from(stake),
+        fee: 0,
+        owner_signature: vec![],
+    };
+    txn.fee = txn
+        .txn_fee(
+            &get_txn_fees(owner.network)
+                .await
+                .map_err(|_| Error::getting_fees())?,
+        )
+        .map_err(|_| Error::getting_fees())?;
+    Ok(txn)
+}
+
+fn print_proposed_transaction(
+    stake: &BlockchainTxnStakeValidatorV1,
+    price: Option<&Price>,
+) -> Result {
     let address = PublicKey::try_from(stake.address.clone())?;
     let units = match address.network {
         Network::TestNet => "TNT",
@@ -141,12 +212,18 @@ fn print_proposed_transaction(stake: &BlockchainTxnStakeValidatorV1) -> Result {
 
     let mut table = Table::new();
     println!("Creating the following stake transaction:");
-    table.add_row(row![
+    let mut titles = row![
         &format!("Stake Amount {}", units),
         "Validator Address",
         "DC Fee"
-    ]);
-    table.add_row(row![Hnt::from(stake.stake), address, stake.fee]);
+    ];
+    let mut values = row![Hnt::from(stake.stake), labeled(&address), stake.fee];
+    if let Some(price) = price {
+        titles.add_cell(cell!("Value USD"));
+        values.add_cell(cell!(price.fmt_usd(Hnt::from(stake.stake).get_decimal())));
+    }
+    table.add_row(titles);
+    table.add_row(values);
     table.printstd();
     println!(
         "WARNING: do not use this output as the source of truth. Instead, rely \
@@ -155,10 +232,17 @@ fn print_proposed_transaction(stake: &BlockchainTxnStakeValidatorV1) -> Result {
     Ok(())
 }
 
+/// Reads a multi validator file
+pub(super) fn read_validators(path: &Path) -> Result<Vec<Validator>> {
+    let file = std::fs::File::open(path)?;
+    let validators: Vec<Validator> = serde_json::from_reader(file)?;
+    Ok(validators)
+}
+
 #[derive(Debug, Deserialize, StructOpt, Clone)]
 pub struct Validator {
-    /// The validator address to stake
-    address: PublicKey,
+    /// The validator address to stake, or an @label from the address book
+    pub(crate) address: Address,
     /// The amount of HNT to stake
-    stake: Hnt,
+    pub(crate) stake: Hnt,
 }
@@ -1,5 +1,14 @@
-use crate::cmd::*;
-use helium_wallet::traits::B64;
+use crate::{
+    cmd::*,
+    partial::{self, PartiallySigned},
+};
+use chrono::Duration;
+use std::{
+    fs,
+    io::{self, Read},
+    num::NonZeroU32,
+    path::PathBuf,
+};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, StructOpt)]
@@ -16,22 +25,22 @@ pub enum Cmd {
 pub struct Create {
     /// The validator to transfer the stake from
     #[structopt(long)]
-    old_address: PublicKey,
+    old_address: Address,
 
     /// The validator to transfer the stake to
     #[structopt(long)]
-    new_address: PublicKey,
+    new_address: Address,
 
     /// The new owner of the transferred validator and stake. If not present
     /// the new owner is assumed to be the same as the current owner as defined
     /// on the blockchain.
     #[structopt(long)]
-    new_owner: Option<PublicKey>,
+    new_owner: Option<Address>,
 
     /// The current (old) owner of the transferred validator and stake. If not present
     /// the old owner is set to the public key of the given wallet.
     #[structopt(long)]
-    old_owner: Option<PublicKey>,
+    old_owner: Option<Address>,
 
     /// The amount of HNT to transfer from the new to the old owner as part of
     /// the stake transfer
@@ -41,6 +50,38 @@ pub struct Create {
     /// The amount of HNT of the original stake
     #[structopt(long)]
     stake_amount: Option<Hnt>,
+
+    /// Write the transaction for the counter-party to this file as a
+    /// partially signed transaction container, with its signer roles, expiry
+    /// and notes. Without it the base64 transaction is printed.
+    #[structopt(long)]
+    output: Option<PathBuf>,
+
+    /// Also display the base64 transaction as a QR code
+    #[structopt(long)]
+    qr: bool,
+
+    /// Note for the counter-party, included in the --output container
+    #[structopt(long, requires = "output")]
+    note: Option<String>,
+
+    /// Hours after which the --output container expires. Must be at least 1
+    #[structopt(long, default_value = "72")]
+    expires_in: NonZeroU32,
+
+    /// Ledger account index of the old owner. Together with
+    /// --new-owner-account this signs as both owners in one session and
+    /// submits the transfer
+    #[structopt(
+        long,
+        requires = "new-owner-account",
+        conflicts_with_all = &["old-owner", "new-owner"]
+    )]
+    old_owner_account: Option<u8>,
+
+    /// Ledger account index of the new owner, see --old-owner-account
+    #[structopt(long, requires = "old-owner-account")]
+    new_owner_account: Option<u8>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -48,18 +89,21 @@ pub struct Create {
 /// the API. The transaction is signed as either (or both) the new owner or the
 /// old owner if the owner keys match the public key of the given wallet.
 pub struct Accept {
-    /// Base64 encoded transaction to sign. If no transaction if given
-    /// stdin is read for the transaction. Note that the stdin feature
-    /// only works if the wallet password is set in the
-    /// HELIUM_WALLET_PASSWORD environment variable
+    /// Transaction to sign, either base64 encoded or as written by `create
+    /// --output`. If neither a transaction nor --input is given, the
+    /// transaction is read from stdin.
     #[structopt(name = "TRANSACTION")]
     txn: Option<Transaction>,
+
+    /// File to read the transaction to sign from
+    #[structopt(long, conflicts_with = "TRANSACTION")]
+    input: Option<PathBuf>,
 }
 
 impl Cmd {
-    pub async fn run(self, opts: Opts, _version: Version) -> Result<Option<(String, Network)>> {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
         match self {
-            Cmd::Create(create) => match ledger_create(opts, create).await? {
+            Cmd::Create(create) => match ledger_create(opts, &version, create).await? {
                 Some(Response::Txn(_txn, hash, network)) => Ok(Some((hash, network))),
                 Some(Response::InsufficientHntBalance(balance, send_request)) => {
                     println!(
@@ -74,7 +118,7 @@ impl Cmd {
                 }
                 _ => Ok(None),
             },
-            Cmd::Accept(accept) => match ledger_accept(opts, accept).await? {
+            Cmd::Accept(accept) => match ledger_accept(opts, &version, accept).await? {
                 Some(Response::Txn(_txn, hash, network)) => Ok(Some((hash, network))),
                 Some(Response::InsufficientHntBalance(balance, send_request)) => {
                     println!(
@@ -93,23 +137,83 @@ impl Cmd {
     }
 }
 
+impl Create {
+    /// Builds the unsigned transfer this command describes between the given
+    /// owners
+    pub(crate) async fn build_txn(
+        &self,
+        old_owner: &PublicKey,
+        new_owner: &PublicKey,
+    ) -> Result<BlockchainTxnTransferValidatorStakeV1> {
+        let old_address = self.old_address.resolve(old_owner.network)?;
+        let new_address = self.new_address.resolve(old_owner.network)?;
+        let mut txn = BlockchainTxnTransferValidatorStakeV1 {
+            new_owner: new_owner.to_vec(),
+            old_owner: old_owner.to_vec(),
+            new_address: new_address.to_vec(),
+            old_address: old_address.to_vec(),
+            fee: 0,
+            payment_amount: u64::from(self.payment),
+            stake_amount: if let Some(stake_amount) = self.stake_amount {
+                u64::from(stake_amount)
+            } else {
+                crate::api::validator(old_owner.network, &old_address.to_string())
+                    .await?
+                    .stake
+                    .into()
+            },
+            new_owner_signature: vec![],
+            old_owner_signature: vec![],
+        };
+        txn.fee = txn
+            .txn_fee(
+                &get_txn_fees(old_owner.network)
+                    .await
+                    .map_err(|_| Error::getting_fees())?,
+            )
+            .map_err(|_| Error::getting_fees())?;
+        Ok(txn)
+    }
+
+    /// The old and new owner given by address, falling back to `account`.
+    /// Owners given as Ledger accounts are not supported here.
+    pub(crate) fn owners(&self, account: &PublicKey) -> Result<(PublicKey, PublicKey)> {
+        if self.old_owner_account.is_some() {
+            return Err(Error::Validator(
+                "give the owners with --old-owner and --new-owner instead of Ledger accounts"
+                    .to_string(),
+            ));
+        }
+        let resolve = |owner: &Option<Address>| match owner {
+            Some(owner) => owner.resolve(account.network),
+            None => Ok(account.clone()),
+        };
+        Ok((resolve(&self.old_owner)?, resolve(&self.new_owner)?))
+    }
+}
+
 pub async fn ledger_create(
     opts: Opts,
+    version: &Version,
     txfer_stake: Create,
 ) -> Result<Option<Response<BlockchainTxnTransferValidatorStakeV1>>> {
     let ledger = get_ledger_transport(&opts).await?;
-    let this_wallet = get_pubkey(opts.account, &ledger, PubkeyDisplay::Off).await?;
+    // with both owner accounts given, the old owner's account signs first
+    let account = txfer_stake.old_owner_account.unwrap_or(opts.account);
+    let this_wallet = get_pubkey(account, &ledger, PubkeyDisplay::Off).await?;
 
     // old_owner defaults to self if not input
-    let old_owner = if let Some(old_owner) = txfer_stake.old_owner {
-        old_owner
+    let old_owner = if let Some(old_owner) = &txfer_stake.old_owner {
+        old_owner.resolve(this_wallet.network)?
     } else {
         this_wallet.clone()
     };
 
     // old_owner defaults to self if not input
-    let new_owner = if let Some(new_owner) = txfer_stake.new_owner {
-        new_owner
+    let new_owner = if let Some(new_owner) = &txfer_stake.new_owner {
+        new_owner.resolve(this_wallet.network)?
+    } else if let Some(new_owner_account) = txfer_stake.new_owner_account {
+        get_pubkey(new_owner_account, &ledger, PubkeyDisplay::Off).await?
     } else {
         this_wallet.clone()
     };
@@ -120,54 +224,54 @@ pub async fn ledger_create(
         return Ok(None);
     }
 
-    let client = new_client(old_owner.network);
-    // calculate fee
-    let mut txn = BlockchainTxnTransferValidatorStakeV1 {
-        new_owner: new_owner.to_vec(),
-        old_owner: old_owner.to_vec(),
-        new_address: txfer_stake.new_address.to_vec(),
-        old_address: txfer_stake.old_address.to_vec(),
-        fee: 0,
-        payment_amount: u64::from(txfer_stake.payment),
-        stake_amount: if let Some(stake_amount) = txfer_stake.stake_amount {
-            u64::from(stake_amount)
-        } else {
-            helium_api::validators::get(&client, &txfer_stake.old_address.to_string())
-                .await?
-                .stake
-                .into()
-        },
-        new_owner_signature: vec![],
-        old_owner_signature: vec![],
-    };
-
-    txn.fee = txn
-        .txn_fee(
-            &get_txn_fees(&client)
-                .await
-                .map_err(|_| Error::getting_fees())?,
-        )
-        .map_err(|_| Error::getting_fees())?;
-
-    print_proposed_transaction(&txn)?;
+    let txn = txfer_stake.build_txn(&old_owner, &new_owner).await?;
 
-    let cmd = txn.apdu_serialize(opts.account)?;
-    let result = read_from_ledger(&ledger, cmd).await?;
-    if result.data.len() == 1 {
-        return Ok(Some(Response::UserDeniedTransaction));
+    super::checks::check_transfer(this_wallet.network, &txn).await?;
+    preflight::check(version, &opts, &this_wallet, std::slice::from_ref(&txn)).await?;
+    if txfer_stake.new_owner_account.is_some() && new_owner != old_owner {
+        // the new owner's account signs as well and pays the old owner, so
+        // its nonces, balance and spending policy are checked too
+        preflight::check(version, &opts, &new_owner, std::slice::from_ref(&txn)).await?;
     }
-    let data = result.data;
+    let price = Price::for_opts(&opts, this_wallet.network).await?;
+    print_proposed_transaction(&txn, price.as_ref())?;
 
-    let mut txn = BlockchainTxnTransferValidatorStakeV1::decode(data.as_slice())?;
+    let unsigned = txn;
+    let mut txn = match sign(&ledger, &unsigned, account).await? {
+        Some(signed) => signed,
+        None => return Ok(Some(Response::UserDeniedTransaction)),
+    };
 
     // A create transfer can only be submitted if we are both old and new owners
-    if old_owner == new_owner {
+    if let (Some(new_owner_account), true) = (txfer_stake.new_owner_account, old_owner != new_owner)
+    {
+        println!(
+            "Confirm the transaction again to sign as the new owner (account {})",
+            new_owner_account
+        );
+        let signed = match sign(&ledger, &unsigned, new_owner_account).await? {
+            Some(signed) => signed,
+            None => return Ok(Some(Response::UserDeniedTransaction)),
+        };
+        txn.new_owner_signature = signed.new_owner_signature;
+
+        // submit the signed transaction to the API, journaled for both
+        // signing accounts
+        let pending_txn_status =
+            submit_signed_by(&[account, new_owner_account], version, &txn).await?;
+
+        Ok(Some(Response::Txn(
+            txn,
+            pending_txn_status.hash,
+            old_owner.network,
+        )))
+    } else if old_owner == new_owner {
         // The APDU txn only passes the signature once as old_owner so as to avoid multiple APDU
         // frames. We make the copy here to accommodate
         txn.new_owner_signature = txn.old_owner_signature.clone();
 
         // submit the signed transaction to the API
-        let pending_txn_status = submit_txn(&client, &txn.in_envelope()).await?;
+        let pending_txn_status = submit_signed_by(&[account], version, &txn).await?;
 
         Ok(Some(Response::Txn(
             txn,
@@ -175,20 +279,62 @@ pub async fn ledger_create(
             old_owner.network,
         )))
     } else {
-        println!("Provide the following base64 output to the counter-party for counter-signing: ");
-        println!("{}", txn.in_envelope().to_b64().unwrap());
+        crate::journal::record(account, version, &txn, crate::journal::Status::Signed, None)?;
+        match &txfer_stake.output {
+            Some(path) => {
+                let mut container = PartiallySigned::new(
+                    &txn,
+                    old_owner.network,
+                    Some(Duration::hours(i64::from(txfer_stake.expires_in.get()))),
+                )?;
+                if let Some(note) = &txfer_stake.note {
+                    let role = if this_wallet == old_owner {
+                        "old_owner"
+                    } else {
+                        "new_owner"
+                    };
+                    container.add_note(role, note);
+                }
+                container.save(path)?;
+                println!(
+                    "Provide {} to the counter-party for counter-signing",
+                    path.display()
+                );
+            }
+            None => {
+                println!(
+                    "Provide the following base64 output to the counter-party for counter-signing: "
+                );
+                println!("{}", txn.in_envelope().to_b64().unwrap());
+            }
+        }
+        if txfer_stake.qr {
+            print_qr(&txn.in_envelope().to_b64().unwrap())?;
+        }
         Ok(None)
     }
 }
 
 pub async fn ledger_accept(
     opts: Opts,
+    version: &Version,
     accept: Accept,
 ) -> Result<Option<Response<BlockchainTxnTransferValidatorStakeV1>>> {
-    let read = read_txn(&accept.txn)?;
+    let Transaction(read, mut container) = read_txn(&accept)?;
+    if let Some(container) = &container {
+        container.print();
+        if container.network != version.network.to_string() {
+            return Err(Error::NetworkMismatch(format!(
+                "transaction was created for {} but the Ledger is on {}",
+                container.network, version.network
+            )));
+        }
+    }
     let mut input_txn = BlockchainTxnTransferValidatorStakeV1::from_envelope(&read)
         .map_err(|_| Error::into_envelope())?;
-    print_proposed_transaction(&input_txn)?;
+    let network = PublicKey::try_from(input_txn.old_owner.clone())?.network;
+    let price = Price::for_opts(&opts, network).await?;
+    print_proposed_transaction(&input_txn, price.as_ref())?;
 
     let old_owner = PublicKey::try_from(input_txn.old_owner.clone())?;
     let new_owner = PublicKey::try_from(input_txn.new_owner.clone())?;
@@ -204,6 +350,15 @@ pub async fn ledger_accept(
         return Ok(None);
     }
 
+    super::checks::check_transfer(this_wallet.network, &input_txn).await?;
+    preflight::check(
+        version,
+        &opts,
+        &this_wallet,
+        std::slice::from_ref(&input_txn),
+    )
+    .await?;
+
     let cmd = input_txn.apdu_serialize(opts.account)?;
     let result = read_from_ledger(&ledger, cmd).await?;
 
@@ -227,9 +382,13 @@ pub async fn ledger_accept(
         input_txn.new_owner_signature = txn.new_owner_signature;
     }
 
+    // make sure signing changed nothing but the signatures
+    if let Some(container) = &mut container {
+        container.update(&input_txn)?;
+    }
+
     // submit the signed transaction to the API
-    let client = new_client(old_owner.network);
-    let pending_txn_status = submit_txn(&client, &input_txn.in_envelope()).await?;
+    let pending_txn_status = submit_signed(&opts, version, &input_txn).await?;
 
     Ok(Some(Response::Txn(
         input_txn,
@@ -238,32 +397,57 @@ pub async fn ledger_accept(
     )))
 }
 
+/// Has the Ledger sign the transfer with the given account. The signature
+/// is returned in the field of the owner role the account holds. Returns None
+/// if the user denied the transaction.
+#[allow(clippy::borrowed_box)]
+async fn sign(
+    ledger: &Box<dyn LedgerTransport>,
+    txn: &BlockchainTxnTransferValidatorStakeV1,
+    account: u8,
+) -> Result<Option<BlockchainTxnTransferValidatorStakeV1>> {
+    let cmd = txn.apdu_serialize(account)?;
+    let result = read_from_ledger(ledger, cmd).await?;
+    if result.data.len() == 1 {
+        return Ok(None);
+    }
+    Ok(Some(BlockchainTxnTransferValidatorStakeV1::decode(
+        result.data.as_slice(),
+    )?))
+}
+
+/// A transaction to accept, either base64 encoded or in a partially signed
+/// transaction container written by `create`
 #[derive(Debug, Clone)]
-pub struct Transaction(BlockchainTxn);
+pub struct Transaction(BlockchainTxn, Option<PartiallySigned>);
 
 impl std::str::FromStr for Transaction {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        Ok(Self(
-            BlockchainTxn::from_b64(s).map_err(|_| Error::from_b64())?,
-        ))
+        let (txn, container) = partial::parse(s)?;
+        Ok(Self(txn, container))
     }
 }
 
-use std::io;
-fn read_txn(txn: &Option<Transaction>) -> Result<BlockchainTxn> {
-    match txn {
-        Some(txn) => Ok(txn.0.clone()),
-        None => {
+/// Reads the transaction to accept from the command line, the --input file
+/// or stdin, in that order
+fn read_txn(accept: &Accept) -> Result<Transaction> {
+    match (&accept.txn, &accept.input) {
+        (Some(txn), _) => Ok(txn.clone()),
+        (None, Some(path)) => fs::read_to_string(path)?.parse(),
+        (None, None) => {
             let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer)?;
-            Ok(buffer.trim().parse::<Transaction>()?.0)
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer.parse()
         }
     }
 }
 
-fn print_proposed_transaction(txn: &BlockchainTxnTransferValidatorStakeV1) -> Result {
+fn print_proposed_transaction(
+    txn: &BlockchainTxnTransferValidatorStakeV1,
+    price: Option<&Price>,
+) -> Result {
     let old_address = PublicKey::try_from(txn.old_address.clone())?;
 
     let units = match old_address.network {
@@ -275,28 +459,40 @@ fn print_proposed_transaction(txn: &BlockchainTxnTransferValidatorStakeV1) -> Re
     println!("Constructing transfer stake transaction:");
     table.add_row(row!["Old Owner", "New Owner",]);
     table.add_row(row![
-        PublicKey::try_from(txn.old_owner.clone())?,
-        PublicKey::try_from(txn.new_owner.clone())?,
+        labeled(&PublicKey::try_from(txn.old_owner.clone())?),
+        labeled(&PublicKey::try_from(txn.new_owner.clone())?),
     ]);
     table.printstd();
     table = Table::new();
     table.add_row(row!["Old Address", "New Address",]);
     table.add_row(row![
-        old_address,
-        PublicKey::try_from(txn.new_address.clone())?,
+        labeled(&old_address),
+        labeled(&PublicKey::try_from(txn.new_address.clone())?),
     ]);
     table.printstd();
     table = Table::new();
-    table.add_row(row![
+    let mut titles = row![
         "Payment to Old Owner",
         &format!("Stake Amount {}", units),
         "DC Fee"
-    ]);
-    table.add_row(row![
+    ];
+    let mut values = row![
         Hnt::from(txn.payment_amount),
         Hnt::from(txn.stake_amount),
         txn.fee
-    ]);
+    ];
+    if let Some(price) = price {
+        titles.add_cell(cell!("Payment USD"));
+        titles.add_cell(cell!("Stake USD"));
+        values.add_cell(cell!(
+            price.fmt_usd(Hnt::from(txn.payment_amount).get_decimal())
+        ));
+        values.add_cell(cell!(
+            price.fmt_usd(Hnt::from(txn.stake_amount).get_decimal())
+        ));
+    }
+    table.add_row(titles);
+    table.add_row(values);
     table.printstd();
     println!(
         "WARNING: do not use this output as the source of truth. Instead, rely \
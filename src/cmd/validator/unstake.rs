@@ -1,30 +1,56 @@
+use super::{
+    progress::{Progress, Status},
+    stake::read_validators,
+};
 use crate::cmd::*;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, StructOpt)]
-/// Unstake a given validator. The stake will be in a cooldown period after
+/// Unstake a validator. The stake will be in a cooldown period after
 /// unstaking before the HNT is returned to the owning wallet.
 pub struct Cmd {
-    /// Address of the validator to unstake
-    address: PublicKey,
+    /// Address of the validator to unstake, or an @label from the address book
+    #[structopt(required_unless = "multi")]
+    address: Option<Address>,
 
     /// The amount of HNT of the original stake
     #[structopt(long)]
     stake_amount: Option<Hnt>,
 
-    /// The stake release block height. This should be at least the current
-    /// block height plus the cooldown period, and 5-10 blocks to allow for
-    /// chain processing delays.
+    /// The stake release block height. Defaults to the current block height
+    /// plus the stake_withdrawal_cooldown chain var and --release-margin.
+    /// Heights below the current height plus the cooldown are refused.
     #[structopt(long)]
-    stake_release_height: u64,
+    stake_release_height: Option<u64>,
+
+    /// Blocks added to the minimum release height when it is computed, to
+    /// allow for chain processing delays. With --multi the height of each
+    /// entry is computed just before it is signed
+    #[structopt(long, default_value = "10")]
+    release_margin: u64,
 
     /// Manually set the fee to pay for the transaction
     #[structopt(long)]
     fee: Option<u64>,
+
+    /// Unstake multiple validators listed in a file instead, in the same
+    /// format as `stake multi` where the stake of each entry is the stake
+    /// amount of the validator. The progress is recorded next to the file,
+    /// and a rerun skips entries that were already submitted or are no
+    /// longer staked.
+    #[structopt(
+        long,
+        conflicts_with_all = &["address", "stake-amount", "stake-release-height", "fee"]
+    )]
+    multi: Option<PathBuf>,
 }
 
 impl Cmd {
-    pub async fn run(self, opts: Opts, _version: Version) -> Result<Option<(String, Network)>> {
-        match ledger(opts, self).await? {
+    pub async fn run(self, opts: Opts, version: Version) -> Result<Option<(String, Network)>> {
+        if let Some(path) = &self.multi {
+            return multi(opts, &version, path, self.release_margin).await;
+        }
+        match ledger(opts, &version, self).await? {
             Response::Txn(_txn, hash, network) => Ok(Some((hash, network))),
             Response::UserDeniedTransaction => {
                 println!("Transaction not confirmed");
@@ -34,59 +60,244 @@ impl Cmd {
         }
     }
 }
-pub async fn ledger(opts: Opts, unstake: Cmd) -> Result<Response<BlockchainTxnUnstakeValidatorV1>> {
+
+pub async fn ledger(
+    opts: Opts,
+    version: &Version,
+    unstake: Cmd,
+) -> Result<Response<BlockchainTxnUnstakeValidatorV1>> {
     let ledger = get_ledger_transport(&opts).await?;
 
     // get account from API so we can get nonce and balance
     let owner = get_pubkey(opts.account, &ledger, PubkeyDisplay::Off).await?;
 
-    let client = new_client(owner.network);
+    let txn = unstake.build_txn(&owner).await?;
+
+    super::checks::check_unstake(owner.network, &txn).await?;
+    preflight::check(version, &opts, &owner, std::slice::from_ref(&txn)).await?;
+    let price = Price::for_opts(&opts, owner.network).await?;
+    print_proposed_txn(&txn, price.as_ref())?;
+
+    let txn = match sign(&ledger, &opts, &txn).await? {
+        Some(txn) => txn,
+        None => return Ok(Response::UserDeniedTransaction),
+    };
+    // submit the signed tansaction to the API
+    let pending_txn_status = submit_signed(&opts, version, &txn).await?;
+
+    Ok(Response::Txn(txn, pending_txn_status.hash, owner.network))
+}
+
+impl Cmd {
+    /// Builds the unsigned unstake this command describes for the given owner
+    pub(crate) async fn build_txn(
+        &self,
+        owner: &PublicKey,
+    ) -> Result<BlockchainTxnUnstakeValidatorV1> {
+        let address = match (&self.address, &self.multi) {
+            (Some(address), None) => address.resolve(owner.network)?,
+            _ => {
+                return Err(Error::Validator(
+                    "give the address of a single validator to unstake".to_string(),
+                ))
+            }
+        };
+        let stake_release_height = stake_release_height(
+            owner.network,
+            self.stake_release_height,
+            self.release_margin,
+        )
+        .await?;
+        build_txn(
+            owner,
+            &address,
+            self.stake_amount,
+            stake_release_height,
+            self.fee,
+        )
+        .await
+    }
+}
+
+/// Unstakes the validators listed in the given file, resuming from the
+/// progress of an earlier run
+async fn multi(
+    opts: Opts,
+    version: &Version,
+    path: &Path,
+    release_margin: u64,
+) -> Result<Option<(String, Network)>> {
+    let mut progress = Progress::load(path)?;
+    let validators = read_validators(path)?;
+
+    let ledger = get_ledger_transport(&opts).await?;
+    let owner = get_pubkey(opts.account, &ledger, PubkeyDisplay::Off).await?;
+
+    // skip entries a previous run got through, or that are unstaked
+    // already, and check the rest before anything is signed
+    let mut txns = Vec::with_capacity(validators.len());
+    for validator in validators {
+        let address = validator.address.resolve(owner.network)?;
+        let key = address.to_string();
+        if progress.is_done(&key) {
+            println!("Skipping {}: done in an earlier run", labeled(&address));
+            continue;
+        }
+        let state = super::checks::validator_state(owner.network, &address).await?;
+        if matches!(&state, Some(state) if !state.is_staked() && state.owner == owner.to_string()) {
+            println!("Skipping {}: no longer staked", labeled(&address));
+            progress.record(&key, Status::Skipped, None, None)?;
+            continue;
+        }
+        let stake_release_height =
+            stake_release_height(owner.network, None, release_margin).await?;
+        let txn = build_txn(
+            &owner,
+            &address,
+            Some(validator.stake),
+            stake_release_height,
+            None,
+        )
+        .await?;
+        super::checks::check_unstake(owner.network, &txn).await?;
+        txns.push(txn);
+    }
+    if txns.is_empty() {
+        println!("Nothing left to unstake");
+        return Ok(None);
+    }
+    preflight::check(version, &opts, &owner, &txns).await?;
+    let price = Price::for_opts(&opts, owner.network).await?;
+
+    for txn in txns {
+        let validator = PublicKey::from_bytes(&txn.address)?;
+        let address = validator.to_string();
+        // signing a long batch takes a while, so the release height checked
+        // above may fall below the chain minimum by now. Each entry gets a
+        // fresh height just before it is signed.
+        let stake_release_height =
+            stake_release_height(owner.network, None, release_margin).await?;
+        let txn = build_txn(
+            &owner,
+            &validator,
+            Some(Hnt::from(txn.stake_amount)),
+            stake_release_height,
+            None,
+        )
+        .await?;
+        super::checks::check_unstake(owner.network, &txn).await?;
+        print_proposed_txn(&txn, price.as_ref())?;
 
+        let txn = match sign(&ledger, &opts, &txn).await? {
+            Some(txn) => txn,
+            None => {
+                let error = "denied on the Ledger".to_string();
+                progress.record(&address, Status::Failed, None, Some(error))?;
+                println!("Transaction not confirmed");
+                return Err(Error::txn());
+            }
+        };
+        match submit_signed(&opts, version, &txn).await {
+            Ok(pending_txn_status) => {
+                let hash = Some(pending_txn_status.hash.clone());
+                progress.record(&address, Status::Submitted, hash, None)?;
+                print_txn(pending_txn_status.hash, owner.network)
+            }
+            Err(err) => {
+                progress.record(&address, Status::Failed, None, Some(err.to_string()))?;
+                println!(
+                    "Progress saved to {}. Rerun to continue from this entry",
+                    progress.path().display()
+                );
+                return Err(err);
+            }
+        }
+    }
+    Ok(None)
+}
+
+async fn build_txn(
+    owner: &PublicKey,
+    address: &PublicKey,
+    stake_amount: Option<Hnt>,
+    stake_release_height: u64,
+    fee: Option<u64>,
+) -> Result<BlockchainTxnUnstakeValidatorV1> {
     let mut txn = BlockchainTxnUnstakeValidatorV1 {
         owner: owner.to_vec(),
-        address: unstake.address.to_vec(),
-        stake_amount: if let Some(stake_amount) = unstake.stake_amount {
+        address: address.to_vec(),
+        stake_amount: if let Some(stake_amount) = stake_amount {
             u64::from(stake_amount)
         } else {
-            helium_api::validators::get(&client, &unstake.address.to_string())
+            crate::api::validator(owner.network, &address.to_string())
                 .await?
                 .stake
                 .into()
         },
-        stake_release_height: unstake.stake_release_height,
+        stake_release_height,
         fee: 0,
         owner_signature: vec![],
     };
 
-    txn.fee = if let Some(fee) = unstake.fee {
+    txn.fee = if let Some(fee) = fee {
         fee
     } else {
         txn.txn_fee(
-            &get_txn_fees(&client)
+            &get_txn_fees(owner.network)
                 .await
                 .map_err(|_| Error::getting_fees())?,
         )
         .map_err(|_| Error::getting_fees())?
     };
+    Ok(txn)
+}
 
-    print_proposed_txn(&txn)?;
-
+/// Has the Ledger sign the given unstake. Returns None if the user denied it
+#[allow(clippy::borrowed_box)]
+async fn sign(
+    ledger: &Box<dyn LedgerTransport>,
+    opts: &Opts,
+    txn: &BlockchainTxnUnstakeValidatorV1,
+) -> Result<Option<BlockchainTxnUnstakeValidatorV1>> {
     let cmd = txn.apdu_serialize(opts.account)?;
-    let exchange_pay_tx_result = read_from_ledger(&ledger, cmd).await?;
+    let exchange_pay_tx_result = read_from_ledger(ledger, cmd).await?;
 
     if exchange_pay_tx_result.data.len() == 1 {
-        return Ok(Response::UserDeniedTransaction);
+        return Ok(None);
     }
+    Ok(Some(BlockchainTxnUnstakeValidatorV1::decode(
+        exchange_pay_tx_result.data.as_slice(),
+    )?))
+}
 
-    let txn = BlockchainTxnUnstakeValidatorV1::decode(exchange_pay_tx_result.data.as_slice())?;
-    let envelope = txn.in_envelope();
-    // submit the signed tansaction to the API
-    let pending_txn_status = submit_txn(&client, &envelope).await?;
-
-    Ok(Response::Txn(txn, pending_txn_status.hash, owner.network))
+/// The release height to use for an unstake. A requested height must not be
+/// below the current height plus the cooldown period; without one, that
+/// minimum plus the margin is used.
+async fn stake_release_height(
+    network: Network,
+    requested: Option<u64>,
+    margin: u64,
+) -> Result<u64> {
+    let vars = crate::vars::get(network).await?;
+    let cooldown = vars
+        .get("stake_withdrawal_cooldown")
+        .and_then(|value| value.as_u64())
+        .ok_or_else(|| {
+            Error::Validator("stake_withdrawal_cooldown chain var not found".to_string())
+        })?;
+    let minimum = crate::api::height(network).await? + cooldown;
+    match requested {
+        Some(height) if height < minimum => Err(Error::Validator(format!(
+            "stake release height {} is below the minimum of {} (current height plus \
+            a cooldown of {} blocks)",
+            height, minimum, cooldown
+        ))),
+        Some(height) => Ok(height),
+        None => Ok(minimum + margin),
+    }
 }
 
-pub fn print_proposed_txn(txn: &BlockchainTxnUnstakeValidatorV1) -> Result {
+pub fn print_proposed_txn(txn: &BlockchainTxnUnstakeValidatorV1, price: Option<&Price>) -> Result {
     let owner = PublicKey::try_from(txn.owner.clone())?;
     let units = match owner.network {
         Network::TestNet => "TNT",
@@ -95,18 +306,26 @@ pub fn print_proposed_txn(txn: &BlockchainTxnUnstakeValidatorV1) -> Result {
 
     let mut table = Table::new();
     println!("Creating the following stake transaction:");
-    table.add_row(row![
+    let mut titles = row![
         &format!("Unstake Amount {}", units),
         "Stake Release Height",
         "Validator Address",
         "DC Fee"
-    ]);
-    table.add_row(row![
+    ];
+    let mut values = row![
         Hnt::from(txn.stake_amount),
         txn.stake_release_height,
-        PublicKey::try_from(txn.address.clone())?,
+        labeled(&PublicKey::try_from(txn.address.clone())?),
         txn.fee
-    ]);
+    ];
+    if let Some(price) = price {
+        titles.add_cell(cell!("Value USD"));
+        values.add_cell(cell!(
+            price.fmt_usd(Hnt::from(txn.stake_amount).get_decimal())
+        ));
+    }
+    table.add_row(titles);
+    table.add_row(values);
     table.printstd();
 
     println!(
@@ -0,0 +1,48 @@
+use super::*;
+use crate::{api, vars::Snapshot};
+use std::path::PathBuf;
+
+#[derive(Debug, StructOpt)]
+/// Work with snapshots of the chain vars, which hold the transaction fee
+/// config among others. A snapshot lets an offline machine compute fees and
+/// run chain var checks with --vars exactly as the online network would.
+/// Account state such as balances and nonces is not part of a snapshot.
+pub enum Cmd {
+    /// Save the current chain vars to a file
+    Export(Export),
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    /// File to write the snapshot to
+    path: PathBuf,
+
+    /// Export the testnet vars instead of mainnet
+    #[structopt(long)]
+    testnet: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, _opts: Opts) -> Result<Option<(String, Network)>> {
+        match self {
+            Cmd::Export(export) => {
+                let network = if export.testnet {
+                    Network::TestNet
+                } else {
+                    Network::MainNet
+                };
+                let mut snapshot = Snapshot::fetch(network).await?;
+                snapshot.height = Some(api::height(network).await?);
+                snapshot.save(&export.path)?;
+                println!(
+                    "Saved {} chain vars of {} at block {} to {}",
+                    snapshot.vars.len(),
+                    network,
+                    snapshot.height.unwrap_or_default(),
+                    export.path.display()
+                );
+                Ok(None)
+            }
+        }
+    }
+}
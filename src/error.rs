@@ -40,6 +40,46 @@ pub enum Error {
     SerdeJson(#[from] serde_json::Error),
     #[error("Invalid token type input: {0}")]
     TokenTypeInput(String),
+    #[error("Data directory error: {0}")]
+    DataDir(String),
+    #[error("Address book error: {0}")]
+    AddressBook(String),
+    #[error("Network mismatch: {0}")]
+    NetworkMismatch(String),
+    #[error("Address mismatch: {0}")]
+    AddressMismatch(String),
+    #[error("Invalid payment request: {0}")]
+    PaymentRequest(String),
+    #[error("Invalid account range: {0}. Use a range such as 0-9 or a list such as 0,2,5-7")]
+    AccountRange(String),
+    #[error("Spending policy violated: {0}")]
+    Policy(String),
+    #[error("Journal error: {0}")]
+    Journal(String),
+    #[error("Invalid export format: {0}")]
+    ExportFormat(String),
+    #[error("CSV Error {0}")]
+    Csv(#[from] csv::Error),
+    #[error("HTTP Error {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Resubmit error: {0}")]
+    Resubmit(String),
+    #[error("Invalid date: {0}. Use YYYY-MM-DD or RFC 3339")]
+    Date(String),
+    #[error("Validator error: {0}")]
+    Validator(String),
+    #[error("Insufficient balance: {0}")]
+    Balance(String),
+    #[error("Partially signed transaction error: {0}")]
+    Container(String),
+    #[error("Unexpected API response: {0}")]
+    Api(String),
+    #[error("Price error: {0}")]
+    Price(String),
+    #[error("Chain vars error: {0}")]
+    Vars(String),
+    #[error("Mock API error: {0}")]
+    Mock(String),
 }
 
 impl Error {
@@ -55,4 +95,33 @@ impl Error {
     pub fn from_b64() -> Error {
         Error::FromB64
     }
+
+    /// Whether the error is likely to go away when the request is retried,
+    /// i.e. timeouts, connection failures, rate limiting and server errors
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::HeliumApi(helium_api::Error::Request(err)) | Error::Http(err) => {
+                err.is_timeout()
+                    || err.is_connect()
+                    || err
+                        .status()
+                        .map(|status| {
+                            status.is_server_error()
+                                || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        })
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the API answered that the requested resource does not exist
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::HeliumApi(helium_api::Error::Request(err)) | Error::Http(err) => {
+                err.status() == Some(reqwest::StatusCode::NOT_FOUND)
+            }
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,431 @@
+use super::{
+    cmd::{
+        preflight::Preflight, BlockchainTxnPaymentV2, BlockchainTxnStakeValidatorV1,
+        BlockchainTxnTokenBurnV1, BlockchainTxnTransferValidatorStakeV1,
+        BlockchainTxnUnstakeValidatorV1, Message, PublicKey, TxnEnvelope,
+    },
+    create_data_dir, data_dir, BlockchainTxn, Error, Network, Result, Version,
+};
+use chrono::{DateTime, Utc};
+use helium_proto::blockchain_txn::Txn;
+use helium_wallet::traits::B64;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Transactions that can be recorded in the journal
+pub trait Journaled: Preflight {
+    fn txn_type(&self) -> &'static str;
+    /// The decoded fields of the transaction
+    fn fields(&self) -> Result<Value>;
+    /// The encoded transaction with all signatures cleared, which is what
+    /// every signer signs
+    fn signing_data(&self) -> Result<Vec<u8>>;
+    /// The roles that sign the transaction, with their current signatures
+    fn signers(&self) -> Result<Vec<Signer>>;
+    fn envelope(&self) -> BlockchainTxn;
+
+    /// The transaction hash as computed by the blockchain, i.e. the sha256
+    /// of the transaction with all signatures cleared
+    fn hash(&self) -> Result<String> {
+        use sha2::{Digest, Sha256};
+        Ok(base64::encode_config(
+            Sha256::digest(&self.signing_data()?),
+            base64::URL_SAFE_NO_PAD,
+        ))
+    }
+}
+
+/// A role that signs a transaction, such as the payer or the old owner of a
+/// validator
+#[derive(Debug, Clone)]
+pub struct Signer {
+    pub role: &'static str,
+    pub key: PublicKey,
+    /// Empty if the role has not signed yet
+    pub signature: Vec<u8>,
+}
+
+impl Signer {
+    fn new(role: &'static str, key: &[u8], signature: &[u8]) -> Result<Self> {
+        Ok(Self {
+            role,
+            key: PublicKey::from_bytes(key)?,
+            signature: signature.to_vec(),
+        })
+    }
+
+    pub fn has_signed(&self) -> bool {
+        !self.signature.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Signed but not submitted, e.g. handed off for counter-signing
+    Signed,
+    Submitted,
+    Failed,
+}
+
+/// A single line of the journal. Entries are only ever appended, so a
+/// transaction may appear more than once as its status changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: DateTime<Utc>,
+    /// Ledger account that signed the transaction. None for transactions
+    /// signed elsewhere and only submitted from here
+    pub account: Option<u8>,
+    /// Version of the Helium app that signed the transaction, if signed here
+    pub app_version: Option<String>,
+    pub network: String,
+    pub txn_type: String,
+    pub hash: String,
+    pub fields: Value,
+    /// Base64 encoded transaction envelope
+    pub txn: String,
+    pub status: Status,
+    /// Hash returned by the API on submission, or the submission error
+    pub response: Option<String>,
+}
+
+impl Entry {
+    pub fn new<T: Journaled + ?Sized>(
+        account: Option<u8>,
+        app_version: Option<String>,
+        network: Network,
+        txn: &T,
+        status: Status,
+        response: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            timestamp: Utc::now(),
+            account,
+            app_version,
+            network: network.to_string(),
+            txn_type: txn.txn_type().to_string(),
+            hash: txn.hash()?,
+            fields: txn.fields()?,
+            txn: txn.envelope().to_b64().map_err(|_| Error::txn())?,
+            status,
+            response,
+        })
+    }
+
+    pub fn envelope(&self) -> Result<BlockchainTxn> {
+        BlockchainTxn::from_b64(&self.txn).map_err(|_| Error::from_b64())
+    }
+
+    /// A copy of this entry with a new status, as of now
+    pub fn with_status(&self, status: Status, response: Option<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            status,
+            response,
+            ..self.clone()
+        }
+    }
+}
+
+pub fn path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(JOURNAL_FILE))
+}
+
+/// Appends an entry for the given transaction, signed by the given Ledger
+/// account, to the journal
+pub fn record<T: Journaled + ?Sized>(
+    account: u8,
+    version: &Version,
+    txn: &T,
+    status: Status,
+    response: Option<String>,
+) -> Result {
+    append(&Entry::new(
+        Some(account),
+        Some(version.to_string()),
+        version.network,
+        txn,
+        status,
+        response,
+    )?)
+}
+
+pub fn append(entry: &Entry) -> Result {
+    create_data_dir()?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path()?)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Reads all journal entries in the order they were recorded
+pub fn entries() -> Result<Vec<Entry>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    read_entries(BufReader::new(fs::File::open(path)?))
+}
+
+/// Reads journal lines. A line that does not parse, such as one cut short by
+/// a crash while it was appended, is skipped with a warning rather than
+/// making the whole journal unreadable.
+fn read_entries<R: BufRead>(reader: R) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => eprintln!(
+                "WARNING: skipping unreadable journal line {}: {}",
+                index + 1,
+                err
+            ),
+        }
+    }
+    Ok(entries)
+}
+
+/// Unwraps a transaction envelope into one of the transaction types the
+/// Ledger can sign
+pub fn from_envelope(envelope: &BlockchainTxn) -> Result<Box<dyn Journaled>> {
+    match &envelope.txn {
+        Some(Txn::PaymentV2(txn)) => Ok(Box::new(txn.clone())),
+        Some(Txn::TokenBurn(txn)) => Ok(Box::new(txn.clone())),
+        Some(Txn::StakeValidator(txn)) => Ok(Box::new(txn.clone())),
+        Some(Txn::UnstakeValidator(txn)) => Ok(Box::new(txn.clone())),
+        Some(Txn::TransferValidatorStake(txn)) => Ok(Box::new(txn.clone())),
+        _ => Err(Error::into_envelope()),
+    }
+}
+
+fn encode<T: Message>(unsigned: &T) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    unsigned.encode(&mut data)?;
+    Ok(data)
+}
+
+fn address(bytes: &[u8]) -> Result<String> {
+    Ok(PublicKey::from_bytes(bytes)?.to_string())
+}
+
+impl Journaled for BlockchainTxnPaymentV2 {
+    fn txn_type(&self) -> &'static str {
+        "payment_v2"
+    }
+
+    fn fields(&self) -> Result<Value> {
+        let payments = self
+            .payments
+            .iter()
+            .map(|payment| -> Result<Value> {
+                Ok(json!({
+                    "payee": address(&payment.payee)?,
+                    "amount": payment.amount,
+                    "token_type": payment.token_type,
+                    "memo": payment.memo,
+                }))
+            })
+            .collect::<Result<Vec<Value>>>()?;
+        Ok(json!({
+            "payer": address(&self.payer)?,
+            "payments": payments,
+            "nonce": self.nonce,
+            "fee": self.fee,
+        }))
+    }
+
+    fn signing_data(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = vec![];
+        encode(&unsigned)
+    }
+
+    fn signers(&self) -> Result<Vec<Signer>> {
+        Ok(vec![Signer::new("payer", &self.payer, &self.signature)?])
+    }
+
+    fn envelope(&self) -> BlockchainTxn {
+        self.in_envelope()
+    }
+}
+
+impl Journaled for BlockchainTxnTokenBurnV1 {
+    fn txn_type(&self) -> &'static str {
+        "token_burn_v1"
+    }
+
+    fn fields(&self) -> Result<Value> {
+        Ok(json!({
+            "payer": address(&self.payer)?,
+            "payee": address(&self.payee)?,
+            "amount": self.amount,
+            "memo": self.memo,
+            "nonce": self.nonce,
+            "fee": self.fee,
+        }))
+    }
+
+    fn signing_data(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = vec![];
+        encode(&unsigned)
+    }
+
+    fn signers(&self) -> Result<Vec<Signer>> {
+        Ok(vec![Signer::new("payer", &self.payer, &self.signature)?])
+    }
+
+    fn envelope(&self) -> BlockchainTxn {
+        self.in_envelope()
+    }
+}
+
+impl Journaled for BlockchainTxnStakeValidatorV1 {
+    fn txn_type(&self) -> &'static str {
+        "stake_validator_v1"
+    }
+
+    fn fields(&self) -> Result<Value> {
+        Ok(json!({
+            "owner": address(&self.owner)?,
+            "address": address(&self.address)?,
+            "stake": self.stake,
+            "fee": self.fee,
+        }))
+    }
+
+    fn signing_data(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.owner_signature = vec![];
+        encode(&unsigned)
+    }
+
+    fn signers(&self) -> Result<Vec<Signer>> {
+        Ok(vec![Signer::new(
+            "owner",
+            &self.owner,
+            &self.owner_signature,
+        )?])
+    }
+
+    fn envelope(&self) -> BlockchainTxn {
+        self.in_envelope()
+    }
+}
+
+impl Journaled for BlockchainTxnUnstakeValidatorV1 {
+    fn txn_type(&self) -> &'static str {
+        "unstake_validator_v1"
+    }
+
+    fn fields(&self) -> Result<Value> {
+        Ok(json!({
+            "owner": address(&self.owner)?,
+            "address": address(&self.address)?,
+            "stake_amount": self.stake_amount,
+            "stake_release_height": self.stake_release_height,
+            "fee": self.fee,
+        }))
+    }
+
+    fn signing_data(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.owner_signature = vec![];
+        encode(&unsigned)
+    }
+
+    fn signers(&self) -> Result<Vec<Signer>> {
+        Ok(vec![Signer::new(
+            "owner",
+            &self.owner,
+            &self.owner_signature,
+        )?])
+    }
+
+    fn envelope(&self) -> BlockchainTxn {
+        self.in_envelope()
+    }
+}
+
+impl Journaled for BlockchainTxnTransferValidatorStakeV1 {
+    fn txn_type(&self) -> &'static str {
+        "transfer_validator_stake_v1"
+    }
+
+    fn fields(&self) -> Result<Value> {
+        Ok(json!({
+            "old_owner": address(&self.old_owner)?,
+            "new_owner": address(&self.new_owner)?,
+            "old_address": address(&self.old_address)?,
+            "new_address": address(&self.new_address)?,
+            "stake_amount": self.stake_amount,
+            "payment_amount": self.payment_amount,
+            "fee": self.fee,
+            "old_owner_signed": !self.old_owner_signature.is_empty(),
+            "new_owner_signed": !self.new_owner_signature.is_empty(),
+        }))
+    }
+
+    fn signing_data(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.old_owner_signature = vec![];
+        unsigned.new_owner_signature = vec![];
+        encode(&unsigned)
+    }
+
+    fn signers(&self) -> Result<Vec<Signer>> {
+        Ok(vec![
+            Signer::new("old_owner", &self.old_owner, &self.old_owner_signature)?,
+            Signer::new("new_owner", &self.new_owner, &self.new_owner_signature)?,
+        ])
+    }
+
+    fn envelope(&self) -> BlockchainTxn {
+        self.in_envelope()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(hash: &str) -> String {
+        json!({
+            "timestamp": "2022-01-01T00:00:00Z",
+            "account": 0,
+            "app_version": "2.2.3",
+            "network": "mainnet",
+            "txn_type": "payment_v2",
+            "hash": hash,
+            "fields": {},
+            "txn": "",
+            "status": "submitted",
+            "response": null,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        // a line cut short by a crash while it was appended
+        let truncated = line("b");
+        let truncated = &truncated[..truncated.len() / 2];
+        let journal = format!("{}\n{}\n\n{}\n", line("a"), truncated, line("c"));
+        let entries = read_entries(journal.as_bytes()).unwrap();
+        let hashes: Vec<&str> = entries.iter().map(|entry| entry.hash.as_str()).collect();
+        assert_eq!(hashes, ["a", "c"]);
+    }
+}
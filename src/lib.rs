@@ -10,9 +10,19 @@ pub use ledger_transport::exchange::Exchange as LedgerTransport;
 pub use qr2term::print_qr;
 pub use std::{env, fmt, process};
 pub use structopt::StructOpt;
+pub mod addressbook;
+pub mod api;
 pub mod cmd;
 pub mod error;
+pub mod journal;
 pub mod memo;
+#[cfg(feature = "mock-api")]
+pub mod mock;
+pub mod partial;
+pub mod payment_request;
+pub mod policy;
+pub mod price;
+pub mod vars;
 
 const DEFAULT_TESTNET_BASE_URL: &str = "https://testnet-api.helium.wtf/v1";
 pub static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
@@ -32,6 +42,31 @@ pub struct Opts {
     /// or 40000)
     #[structopt(long = "emulator")]
     pub emulator: Option<u16>,
+
+    /// Proceed even if a transaction violates the local spending policy.
+    /// Every override is logged in the data directory.
+    #[structopt(long = "override-policy")]
+    pub override_policy: bool,
+
+    /// Show the USD value of HNT amounts, using the current oracle price
+    #[structopt(long = "usd")]
+    pub usd: bool,
+
+    /// USD price of one HNT to use instead of the oracle price. Implies --usd
+    #[structopt(long = "price")]
+    pub price: Option<rust_decimal::Decimal>,
+
+    /// Chain vars snapshot written by `vars export` to use instead of
+    /// fetching the vars, e.g. to quote fees with `fee --address` on an
+    /// offline machine. The snapshot only covers fees and chain var checks:
+    /// commands that sign still query the API for balances, nonces and
+    /// validator state.
+    #[structopt(long = "vars")]
+    pub vars: Option<std::path::PathBuf>,
+
+    /// Hours after which a --vars snapshot is reported as stale
+    #[structopt(long = "vars-max-age", default_value = "24")]
+    pub vars_max_age: u32,
 }
 
 #[derive(Debug, StructOpt)]
@@ -53,8 +88,36 @@ pub enum Cmd {
     Burn(cmd::burn::Cmd),
     /// Pay a given address.
     Pay(cmd::pay::Cmd),
+    /// Show and verify the address to receive tokens on
+    Receive(cmd::receive::Cmd),
+    /// Export account public keys for watch-only use
+    ExportPubkeys(cmd::export_pubkeys::Cmd),
     /// Stake a validator
     Validators(cmd::validator::Cmd),
+    /// Show account activity history, exportable as CSV or JSON
+    History(cmd::history::Cmd),
+    /// List pending transactions and nonces of an account
+    Pending(cmd::pending::Cmd),
+    /// Manage labeled addresses usable as @label in place of an address
+    Addressbook(cmd::addressbook::Cmd),
+    /// Inspect the local journal of signed transactions
+    Journal(cmd::journal::Cmd),
+    /// Resubmit a signed transaction that did not make it to the API
+    Resubmit(cmd::resubmit::Cmd),
+    /// Show the current HNT oracle price
+    Price(cmd::price::Cmd),
+    /// Decode and verify a transaction or partially signed transaction
+    Decode(cmd::decode::Cmd),
+    /// Submit a transaction signed by every party
+    Submit(cmd::submit::Cmd),
+    /// Quote the fee of a transaction without signing it. The Ledger is only
+    /// used when --address is not given
+    Fee(cmd::fee::Cmd),
+    /// Export chain vars for offline use
+    Vars(cmd::vars::Cmd),
+    /// Run a local mock of the Helium API for testing
+    #[cfg(feature = "mock-api")]
+    MockApi(cmd::mock_api::Cmd),
     /// Deprecated in favor for Pay with HST
     Securities,
 }
@@ -109,6 +172,30 @@ pub fn print_txn(hash: String, network: Network) {
 
 use helium_api::Client;
 use prettytable::{format, Table};
+use std::path::PathBuf;
+
+/// Directory holding local state such as the address book. Defaults to
+/// `.helium-ledger` in the home directory and can be overridden with the
+/// HELIUM_LEDGER_DIR environment variable. It may not exist yet.
+pub fn data_dir() -> Result<PathBuf> {
+    match env::var("HELIUM_LEDGER_DIR") {
+        Ok(dir) => Ok(PathBuf::from(dir)),
+        Err(_) => env::var("HOME")
+            .or_else(|_| env::var("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".helium-ledger"))
+            .map_err(|_| {
+                Error::DataDir("set HOME or HELIUM_LEDGER_DIR to store local data".to_string())
+            }),
+    }
+}
+
+/// The data directory, created if it does not exist yet. Only needed before
+/// writing to it.
+pub fn create_data_dir() -> Result<PathBuf> {
+    let dir = data_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
 
 pub async fn submit_txn(client: &Client, txn: &BlockchainTxn) -> Result<PendingTxnStatus> {
     use helium_proto::Message;
@@ -119,16 +206,19 @@ pub async fn submit_txn(client: &Client, txn: &BlockchainTxn) -> Result<PendingT
         .map_err(|e| e.into())
 }
 
-fn new_client(network: Network) -> Client {
-    Client::new_with_base_url(api_url(network), USER_AGENT)
+/// Submits a transaction, retrying transient API errors with exponential
+/// backoff and failing over to other endpoints of the network. Resubmitting
+/// is safe since the transaction hash does not change.
+pub async fn submit_txn_with_retry(
+    network: Network,
+    txn: &BlockchainTxn,
+) -> Result<PendingTxnStatus> {
+    api::with_failover(network, |url| async move {
+        submit_txn(&api::client(&url), txn).await
+    })
+    .await
 }
 
 fn api_url(network: Network) -> String {
-    match network {
-        Network::MainNet => {
-            env::var("HELIUM_API_URL").unwrap_or_else(|_| helium_api::DEFAULT_BASE_URL.to_string())
-        }
-        Network::TestNet => env::var("HELIUM_TESTNET_API_URL")
-            .unwrap_or_else(|_| DEFAULT_TESTNET_BASE_URL.to_string()),
-    }
+    api::url(network)
 }
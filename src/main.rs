@@ -2,8 +2,6 @@ use helium_ledger::*;
 
 #[tokio::main]
 async fn main() {
-    println!("Communicating with Ledger - follow prompts on screen");
-
     let cli = Cli::from_args();
     if let Err(e) = run(cli).await {
         println!("error: {}", e);
@@ -12,14 +10,57 @@ async fn main() {
 }
 
 async fn run(cli: Cli) -> Result {
-    let version = cmd::get_app_version(&cli.opts).await?;
-    println!("Ledger running Helium App {}\r\n", version);
-
+    if let Some(path) = &cli.opts.vars {
+        vars::pin(
+            vars::Snapshot::load(path)?,
+            chrono::Duration::hours(i64::from(cli.opts.vars_max_age)),
+        );
+    }
+    let opts = cli.opts;
     let result = match cli.cmd {
-        Cmd::Balance(balance) => balance.run(cli.opts, version).await?,
-        Cmd::Burn(burn) => burn.run(cli.opts, version).await?,
-        Cmd::Pay(pay) => pay.run(cli.opts, version).await?,
-        Cmd::Validators(validator) => validator.run(cli.opts, version).await?,
+        Cmd::Balance(balance) => {
+            let version = connect(&opts).await?;
+            balance.run(opts, version).await?
+        }
+        Cmd::Burn(burn) => {
+            let version = connect(&opts).await?;
+            burn.run(opts, version).await?
+        }
+        Cmd::Pay(pay) => {
+            let version = connect(&opts).await?;
+            pay.run(opts, version).await?
+        }
+        Cmd::Receive(receive) => {
+            let version = connect(&opts).await?;
+            receive.run(opts, version).await?
+        }
+        Cmd::ExportPubkeys(export) => {
+            let version = connect(&opts).await?;
+            export.run(opts, version).await?
+        }
+        Cmd::Validators(validator) => {
+            let version = connect(&opts).await?;
+            validator.run(opts, version).await?
+        }
+        Cmd::History(history) => {
+            let version = connect(&opts).await?;
+            history.run(opts, version).await?
+        }
+        Cmd::Pending(pending) => {
+            let version = connect(&opts).await?;
+            pending.run(opts, version).await?
+        }
+        // commands that do not need to talk to the Ledger
+        Cmd::Addressbook(addressbook) => addressbook.run(opts).await?,
+        Cmd::Journal(journal) => journal.run(opts).await?,
+        Cmd::Resubmit(resubmit) => resubmit.run(opts).await?,
+        Cmd::Price(price) => price.run(opts).await?,
+        Cmd::Decode(decode) => decode.run(opts).await?,
+        Cmd::Submit(submit) => submit.run(opts).await?,
+        Cmd::Fee(fee) => fee.run(opts).await?,
+        Cmd::Vars(vars) => vars.run(opts).await?,
+        #[cfg(feature = "mock-api")]
+        Cmd::MockApi(mock_api) => mock_api.run(opts).await?,
         Cmd::Securities => {
             println!("This command is deprecated in favor of payment_v2 with token type HST");
             None
@@ -31,3 +72,12 @@ async fn run(cli: Cli) -> Result {
 
     Ok(())
 }
+
+/// Connects to the Ledger and reads the version of the Helium app. Progress
+/// goes to stderr to keep exports written to stdout clean.
+async fn connect(opts: &Opts) -> Result<Version> {
+    eprintln!("Communicating with Ledger - follow prompts on screen");
+    let version = cmd::get_app_version(opts).await?;
+    eprintln!("Ledger running Helium App {}\r\n", version);
+    Ok(version)
+}
@@ -0,0 +1,358 @@
+use super::{journal, BlockchainTxn, Error, Result};
+use chrono::{DateTime, Utc};
+use helium_wallet::traits::B64;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    convert::Infallible,
+    fs::{self, OpenOptions},
+    io::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// What the mock API serves. Accounts the fixtures do not list exist with
+/// zero balances, like on the real API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fixtures {
+    /// Current chain height
+    pub height: u64,
+    /// Oracle price in units of 10^-8 USD per HNT
+    pub oracle_price: u64,
+    pub vars: Map<String, Value>,
+    /// Accounts as returned by `/accounts/{address}`. Missing fields are zero
+    pub accounts: Vec<Value>,
+    /// Validators as returned by `/validators/{address}`
+    pub validators: Vec<Value>,
+}
+
+impl Default for Fixtures {
+    fn default() -> Self {
+        let vars = json!({
+            "txn_fees": false,
+            "stake_withdrawal_cooldown": 250_000,
+            "validator_minimum_stake": 1_000_000_000_000u64,
+        });
+        Self {
+            height: 1_000_000,
+            oracle_price: 1_000_000_000,
+            vars: vars.as_object().cloned().unwrap_or_default(),
+            accounts: vec![],
+            validators: vec![],
+        }
+    }
+}
+
+impl Fixtures {
+    pub fn load(path: &Path) -> Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| Error::Mock(format!("invalid fixtures {}: {}", path.display(), err)))
+    }
+}
+
+/// A transaction submitted to the mock API
+#[derive(Debug, Clone, Serialize)]
+pub struct Submission {
+    pub hash: String,
+    pub txn_type: String,
+    /// Base64 encoded transaction envelope, as submitted
+    pub txn: String,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Makes the next `count` requests whose path starts with `path` fail with
+/// `status`, after an optional delay to simulate a slow API. A count of 0
+/// is refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    /// Path without the `/v1` prefix, e.g. `/pending_transactions`
+    pub path: String,
+    pub status: u16,
+    #[serde(default = "default_count")]
+    pub count: usize,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+#[derive(Debug, Default)]
+struct State {
+    fixtures: Fixtures,
+    submissions: Vec<Submission>,
+    failures: Vec<Failure>,
+    record: Option<PathBuf>,
+}
+
+/// A Helium API mock serving fixtures over HTTP in the background, for
+/// running the CLI hermetically. Point HELIUM_API_URL or
+/// HELIUM_TESTNET_API_URL at `url()`.
+///
+/// Besides the API endpoints the application uses, it serves
+/// `GET /v1/mock/submissions` listing every submitted transaction and
+/// `POST /v1/mock/failures` taking a `Failure` to inject.
+pub struct MockApi {
+    state: Arc<Mutex<State>>,
+    url: String,
+}
+
+impl MockApi {
+    /// Starts serving the fixtures on the given address. Port 0 picks a free
+    /// port. Must be called from within a Tokio runtime.
+    pub fn start(fixtures: Fixtures, addr: SocketAddr) -> Result<Self> {
+        let state = Arc::new(Mutex::new(State {
+            fixtures,
+            ..Default::default()
+        }));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            let service = service_fn(move |request| handle(state.clone(), request));
+            async move { Ok::<_, Infallible>(service) }
+        });
+        let server = Server::try_bind(&addr)
+            .map_err(|err| Error::Mock(format!("unable to listen on {}: {}", addr, err)))?
+            .serve(make_service);
+        let url = format!("http://{}/v1", server.local_addr());
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                println!("WARNING: mock API stopped: {}", err);
+            }
+        });
+        Ok(Self { state, url })
+    }
+
+    /// Base URL of the mock API, including the `/v1` prefix
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Appends every submitted transaction to the given file as a line of
+    /// JSON
+    pub fn record_to(&self, path: PathBuf) {
+        self.state().record = Some(path);
+    }
+
+    /// Every transaction submitted so far
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.state().submissions.clone()
+    }
+
+    pub fn fail(&self, failure: Failure) -> Result {
+        if failure.count == 0 {
+            return Err(Error::Mock("failure count must be at least 1".to_string()));
+        }
+        self.state().failures.push(failure);
+        Ok(())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let uri_path = request.uri().path().to_string();
+    let path = uri_path
+        .strip_prefix("/v1")
+        .unwrap_or(&uri_path)
+        .trim_end_matches('/')
+        .to_string();
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+
+    let failure = state
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .take_failure(&path);
+    let (status, value) = match failure {
+        Some(failure) => {
+            tokio::time::sleep(Duration::from_millis(failure.delay_ms)).await;
+            (
+                StatusCode::from_u16(failure.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                json!({ "error": "injected failure" }),
+            )
+        }
+        None => state
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .route(&method, &path, &body),
+    };
+
+    let mut response = Response::new(Body::from(value.to_string()));
+    *response.status_mut() = status;
+    if let Ok(content_type) = "application/json".parse() {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    Ok(response)
+}
+
+fn data(data: Value) -> (StatusCode, Value) {
+    (StatusCode::OK, json!({ "data": data }))
+}
+
+fn not_found() -> (StatusCode, Value) {
+    (StatusCode::NOT_FOUND, json!({ "error": "not found" }))
+}
+
+fn bad_request(error: String) -> (StatusCode, Value) {
+    (StatusCode::BAD_REQUEST, json!({ "error": error }))
+}
+
+impl State {
+    fn take_failure(&mut self, path: &str) -> Option<Failure> {
+        let index = self
+            .failures
+            .iter()
+            .position(|failure| path.starts_with(&failure.path))?;
+        let failure = self.failures[index].clone();
+        if failure.count <= 1 {
+            self.failures.remove(index);
+        } else {
+            self.failures[index].count -= 1;
+        }
+        Some(failure)
+    }
+
+    fn route(&mut self, method: &Method, path: &str, body: &[u8]) -> (StatusCode, Value) {
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (&Method::GET, ["accounts", address]) => data(self.account(address)),
+            (&Method::GET, ["accounts", address, "validators"]) => data(Value::Array(
+                self.fixtures
+                    .validators
+                    .iter()
+                    .filter(|validator| validator.get("owner") == Some(&json!(address)))
+                    .cloned()
+                    .collect(),
+            )),
+            (&Method::GET, ["accounts", _, _]) => data(json!([])),
+            (&Method::GET, ["validators", address]) => self
+                .fixtures
+                .validators
+                .iter()
+                .find(|validator| validator.get("address") == Some(&json!(address)))
+                .cloned()
+                .map(data)
+                .unwrap_or_else(not_found),
+            (&Method::GET, ["validators", _, "activity"]) => data(json!([])),
+            (&Method::GET, ["validators", _, "rewards", "sum"]) => {
+                data(json!({ "sum": 0, "total": 0.0 }))
+            }
+            (&Method::GET, ["vars"]) => data(Value::Object(self.fixtures.vars.clone())),
+            (&Method::GET, ["oracle", "prices", "current"]) => data(json!({
+                "price": self.fixtures.oracle_price,
+                "block": self.fixtures.height,
+            })),
+            (&Method::GET, ["blocks", "height"]) => data(json!({ "height": self.fixtures.height })),
+            (&Method::GET, ["pending_transactions", hash]) => {
+                match self.submissions.iter().find(|s| s.hash == *hash) {
+                    Some(submission) => data(json!([{
+                        "hash": submission.hash,
+                        "type": submission.txn_type,
+                        "status": "pending",
+                        "created_at": submission.received_at,
+                        "failed_reason": "",
+                    }])),
+                    None => not_found(),
+                }
+            }
+            (&Method::POST, ["pending_transactions"]) => self.submit(body),
+            (&Method::GET, ["mock", "submissions"]) => data(json!(self.submissions)),
+            (&Method::POST, ["mock", "failures"]) => {
+                match serde_json::from_slice::<Failure>(body) {
+                    Ok(failure) if failure.count == 0 => {
+                        bad_request("failure count must be at least 1".to_string())
+                    }
+                    Ok(failure) => {
+                        self.failures.push(failure);
+                        data(json!({}))
+                    }
+                    Err(err) => bad_request(err.to_string()),
+                }
+            }
+            _ => not_found(),
+        }
+    }
+
+    /// The fixture of an account with any missing fields set to zero
+    fn account(&self, address: &str) -> Value {
+        let mut account = json!({
+            "address": address,
+            "balance": 0,
+            "staked_balance": 0,
+            "dc_balance": 0,
+            "sec_balance": 0,
+            "iot_balance": 0,
+            "mobile_balance": 0,
+            "nonce": 0,
+            "speculative_nonce": 0,
+            "sec_nonce": 0,
+            "speculative_sec_nonce": 0,
+            "block": self.fixtures.height,
+        });
+        let fixture = self
+            .fixtures
+            .accounts
+            .iter()
+            .find(|account| account.get("address") == Some(&json!(address)))
+            .and_then(Value::as_object);
+        if let (Some(account), Some(fixture)) = (account.as_object_mut(), fixture) {
+            account.extend(fixture.clone());
+        }
+        account
+    }
+
+    fn submit(&mut self, body: &[u8]) -> (StatusCode, Value) {
+        let submission = match parse_submission(body) {
+            Ok(submission) => submission,
+            Err(err) => return bad_request(err.to_string()),
+        };
+        if let Some(path) = &self.record {
+            let appended = serde_json::to_string(&submission)
+                .map_err(Error::from)
+                .and_then(|line| {
+                    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                    writeln!(file, "{}", line)?;
+                    Ok(())
+                });
+            if let Err(err) = appended {
+                println!("WARNING: unable to record submission: {}", err);
+            }
+        }
+        let hash = submission.hash.clone();
+        self.submissions.push(submission);
+        data(json!({ "hash": hash }))
+    }
+}
+
+fn parse_submission(body: &[u8]) -> Result<Submission> {
+    let body: Value = serde_json::from_slice(body)?;
+    let txn = body
+        .get("txn")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::Mock("missing txn".to_string()))?;
+    let envelope = BlockchainTxn::from_b64(txn).map_err(|_| Error::from_b64())?;
+    let journaled = journal::from_envelope(&envelope)?;
+    Ok(Submission {
+        hash: journaled.hash()?,
+        txn_type: journaled.txn_type().to_string(),
+        txn: txn.to_string(),
+        received_at: Utc::now(),
+    })
+}
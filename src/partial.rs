@@ -0,0 +1,304 @@
+use super::{
+    cmd::Preflight,
+    journal::{self, Journaled},
+    BlockchainTxn, Error, Network, Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use helium_crypto::Verify;
+use helium_wallet::traits::B64;
+use prettytable::Table;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, str::FromStr};
+
+/// Marks a JSON document as a partially signed transaction
+const FORMAT: &str = "helium-partially-signed-txn";
+/// The container version written by this application. Containers of newer
+/// versions are refused.
+const VERSION: u32 = 1;
+
+/// A transaction that needs signatures from more than one party, with
+/// everything a party needs to know before signing it. The hash, the roles
+/// and all signatures are checked against the enclosed transaction when the
+/// container is opened, and the network against its addresses. The expiry
+/// and the notes are not part of the signed transaction, so they are only
+/// as trustworthy as the channel the container was received over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartiallySigned {
+    pub format: String,
+    pub version: u32,
+    pub network: String,
+    pub txn_type: String,
+    /// Hash of the transaction, which changes if anything but the signatures
+    /// is changed
+    pub hash: String,
+    /// Base64 encoded transaction envelope
+    pub txn: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Every role that has to sign before the transaction can be submitted
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Role {
+    pub role: String,
+    pub address: String,
+    pub signed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    /// Role of the party that left the note
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub text: String,
+}
+
+impl PartiallySigned {
+    pub fn new(
+        txn: &dyn Journaled,
+        network: Network,
+        expires_in: Option<Duration>,
+    ) -> Result<Self> {
+        let created_at = Utc::now();
+        Ok(Self {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            network: network.to_string(),
+            txn_type: txn.txn_type().to_string(),
+            hash: txn.hash()?,
+            txn: txn.envelope().to_b64().map_err(|_| Error::txn())?,
+            created_at,
+            expires_at: expires_in.map(|expires_in| created_at + expires_in),
+            roles: roles(txn)?,
+            notes: vec![],
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add_note(&mut self, role: &str, text: &str) {
+        self.notes.push(Note {
+            role: role.to_string(),
+            created_at: Utc::now(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Replaces the enclosed transaction with a copy that carries more
+    /// signatures. Anything else changing is refused.
+    pub fn update(&mut self, txn: &dyn Journaled) -> Result {
+        if txn.hash()? != self.hash {
+            return Err(Error::Container(
+                "updated transaction differs from the enclosed one".to_string(),
+            ));
+        }
+        self.txn = txn.envelope().to_b64().map_err(|_| Error::txn())?;
+        self.roles = roles(txn)?;
+        Ok(())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at < Utc::now())
+    }
+
+    /// Verifies the container against the enclosed transaction and returns
+    /// the transaction
+    pub fn open(&self) -> Result<Box<dyn Journaled>> {
+        if self.format != FORMAT {
+            return Err(Error::Container(format!("unknown format {}", self.format)));
+        }
+        if self.version > VERSION {
+            return Err(Error::Container(format!(
+                "version {} is newer than the supported version {}. Consider updating",
+                self.version, VERSION
+            )));
+        }
+        if let Some(expires_at) = self.expires_at.filter(|_| self.is_expired()) {
+            return Err(Error::Container(format!(
+                "expired at {}",
+                expires_at.to_rfc3339()
+            )));
+        }
+        let txn = journal::from_envelope(&self.envelope()?)?;
+        if txn.hash()? != self.hash || txn.txn_type() != self.txn_type {
+            return Err(Error::Container(
+                "transaction does not match its hash or type".to_string(),
+            ));
+        }
+        for address in txn.addresses()? {
+            if address.network.to_string() != self.network {
+                return Err(Error::Container(format!(
+                    "{} is not on {}",
+                    address, self.network
+                )));
+            }
+        }
+        if roles(txn.as_ref())? != self.roles {
+            return Err(Error::Container(
+                "signer roles do not match the transaction".to_string(),
+            ));
+        }
+        let data = txn.signing_data()?;
+        for signer in txn.signers()? {
+            if signer.has_signed() && signer.key.verify(&data, &signer.signature).is_err() {
+                return Err(Error::Container(format!(
+                    "invalid {} signature",
+                    signer.role
+                )));
+            }
+        }
+        Ok(txn)
+    }
+
+    pub fn envelope(&self) -> Result<BlockchainTxn> {
+        BlockchainTxn::from_b64(&self.txn).map_err(|_| Error::from_b64())
+    }
+
+    pub fn print(&self) {
+        let mut table = Table::new();
+        table.add_row(row!["Type", "Network", "Hash", "Created", "Expires"]);
+        table.add_row(row![
+            self.txn_type,
+            self.network,
+            self.hash,
+            self.created_at.to_rfc3339(),
+            self.expires_at
+                .map(|expires_at| expires_at.to_rfc3339())
+                .unwrap_or_else(|| "never".to_string()),
+        ]);
+        table.printstd();
+
+        let mut table = Table::new();
+        table.add_row(row!["Role", "Address", "Signed"]);
+        for role in &self.roles {
+            table.add_row(row![role.role, role.address, role.signed]);
+        }
+        table.printstd();
+
+        for note in &self.notes {
+            println!(
+                "Note from {} ({}): {}",
+                note.role, note.created_at, note.text
+            );
+        }
+    }
+}
+
+impl FromStr for PartiallySigned {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        serde_json::from_str(s).map_err(|err| {
+            Error::Container(format!("invalid partially signed transaction: {}", err))
+        })
+    }
+}
+
+fn roles(txn: &dyn Journaled) -> Result<Vec<Role>> {
+    Ok(txn
+        .signers()?
+        .iter()
+        .map(|signer| Role {
+            role: signer.role.to_string(),
+            address: signer.key.to_string(),
+            signed: signer.has_signed(),
+        })
+        .collect())
+}
+
+/// Parses either a partially signed transaction container or a bare base64
+/// encoded transaction envelope. Containers are verified.
+pub fn parse(s: &str) -> Result<(BlockchainTxn, Option<PartiallySigned>)> {
+    let s = s.trim();
+    if s.starts_with('{') {
+        let container: PartiallySigned = s.parse()?;
+        container.open()?;
+        Ok((container.envelope()?, Some(container)))
+    } else {
+        Ok((
+            BlockchainTxn::from_b64(s).map_err(|_| Error::from_b64())?,
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helium_proto::{BlockchainTxnPaymentV2, Payment};
+    use helium_wallet::keypair::PublicKey;
+
+    /// The Ed25519 base point, which is a valid public key
+    const ED25519_BASE_POINT: [u8; 32] = [
+        0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+        0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+        0x66, 0x66,
+    ];
+
+    fn payment(amount: u64, signature: Vec<u8>) -> BlockchainTxnPaymentV2 {
+        let mut key = vec![0x01];
+        key.extend_from_slice(&ED25519_BASE_POINT);
+        let key = PublicKey::from_bytes(&key).unwrap().to_vec();
+        BlockchainTxnPaymentV2 {
+            payer: key.clone(),
+            payments: vec![Payment {
+                payee: key,
+                amount,
+                ..Default::default()
+            }],
+            fee: 35_000,
+            nonce: 1,
+            signature,
+        }
+    }
+
+    fn container() -> PartiallySigned {
+        PartiallySigned::new(&payment(100_000_000, vec![]), Network::MainNet, None).unwrap()
+    }
+
+    /// Whether opening the container fails with an error mentioning `reason`
+    fn refused(container: &PartiallySigned, reason: &str) -> bool {
+        matches!(container.open(), Err(Error::Container(err)) if err.contains(reason))
+    }
+
+    #[test]
+    fn untampered_container_opens() {
+        assert!(container().open().is_ok());
+    }
+
+    #[test]
+    fn tampered_hash_is_detected() {
+        // a changed transaction no longer matches the hash
+        let mut tampered = container();
+        tampered.txn = payment(200_000_000, vec![]).envelope().to_b64().unwrap();
+        assert!(refused(&tampered, "hash"));
+
+        let mut tampered = container();
+        tampered.hash = payment(200_000_000, vec![]).hash().unwrap();
+        assert!(refused(&tampered, "hash"));
+    }
+
+    #[test]
+    fn tampered_role_is_detected() {
+        let mut tampered = container();
+        tampered.roles[0].signed = true;
+        assert!(refused(&tampered, "roles"));
+
+        let mut tampered = container();
+        tampered.roles[0].role = "payee".to_string();
+        assert!(refused(&tampered, "roles"));
+    }
+
+    #[test]
+    fn tampered_signature_is_detected() {
+        let forged = payment(100_000_000, vec![1; 64]);
+        let tampered = PartiallySigned::new(&forged, Network::MainNet, None).unwrap();
+        assert!(refused(&tampered, "signature"));
+    }
+}
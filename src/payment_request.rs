@@ -0,0 +1,258 @@
+use super::{addressbook::labeled, cmd::pay::TokenInput, memo::Memo, Error, Network, Result};
+use helium_api::models::Token;
+use helium_wallet::keypair::PublicKey;
+use prettytable::Table;
+use rust_decimal::Decimal;
+use std::{fmt, str::FromStr};
+
+/// URI scheme of payment requests
+pub const SCHEME: &str = "helium";
+
+/// A request to be paid, shared as a URI such as
+/// `helium:<address>?amount=1.5&token=hnt&memo=<base64>`. Everything but the
+/// address is optional.
+#[derive(Debug)]
+pub struct PaymentRequest {
+    pub address: PublicKey,
+    pub amount: Option<Token>,
+    pub token: TokenInput,
+    pub memo: Option<Memo>,
+}
+
+impl fmt::Display for PaymentRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", SCHEME, self.address)?;
+        let mut params = vec![];
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", amount));
+        }
+        if self.amount.is_some() || self.token != TokenInput::Hnt {
+            params.push(format!("token={}", self.token));
+        }
+        if let Some(memo) = &self.memo {
+            params.push(format!("memo={}", encode(&memo.to_string())));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
+
+impl PaymentRequest {
+    /// Errors if the requested address is not on the given network
+    pub fn check_network(&self, network: Network) -> Result {
+        if self.address.network != network {
+            return Err(Error::NetworkMismatch(format!(
+                "payment request is for a {} address but the Ledger is on {}",
+                self.address.network, network
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn print(&self) {
+        let mut table = Table::new();
+        table.add_row(row!["Payee", "Amount", "Token", "Memo"]);
+        table.add_row(row![
+            labeled(&self.address),
+            self.amount
+                .map(|amount| amount.to_string())
+                .unwrap_or_else(|| "any".to_string()),
+            self.token,
+            self.memo
+                .as_ref()
+                .map(|memo| memo.to_string())
+                .unwrap_or_else(|| "none".to_string()),
+        ]);
+        table.printstd();
+    }
+}
+
+impl FromStr for PaymentRequest {
+    type Err = Error;
+
+    /// Parses a payment request URI. Unknown or repeated parameters are
+    /// refused rather than ignored, since they may change what is paid.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::PaymentRequest(reason);
+        let rest = s
+            .trim()
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(SCHEME))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| invalid(format!("expected a {}: URI", SCHEME)))?;
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut request = PaymentRequest {
+            address: PublicKey::from_str(address)
+                .map_err(|_| invalid(format!("invalid address {}", address)))?,
+            amount: None,
+            token: TokenInput::Hnt,
+            memo: None,
+        };
+
+        let mut seen = vec![];
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("parameter {} has no value", param)))?;
+            if seen.contains(&key) {
+                return Err(invalid(format!("parameter {} is repeated", key)));
+            }
+            seen.push(key);
+            let value = decode(value)?;
+            match key {
+                "amount" => {
+                    let amount = Token::from_str(&value)
+                        .map_err(|_| invalid(format!("invalid amount {}", value)))?;
+                    if amount.get_decimal() <= Decimal::ZERO {
+                        return Err(invalid(format!("amount {} is not positive", value)));
+                    }
+                    request.amount = Some(amount);
+                }
+                "token" => request.token = value.parse()?,
+                "memo" => request.memo = Some(value.parse()?),
+                _ => return Err(invalid(format!("unknown parameter {}", key))),
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// Percent-encodes everything but unreserved URI characters, such as the
+/// `+`, `/` and `=` of base64 memos
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn decode(value: &str) -> Result<String> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+            let byte = std::str::from_utf8(&hex)
+                .ok()
+                .filter(|hex| hex.len() == 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| Error::PaymentRequest(format!("invalid escape in {}", value)))?;
+            decoded.push(byte);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded)
+        .map_err(|_| Error::PaymentRequest(format!("invalid characters in {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An address on the given network, from the Ed25519 base point
+    fn address(network: Network) -> PublicKey {
+        let tag = match network {
+            Network::MainNet => 0x01,
+            Network::TestNet => 0x11,
+        };
+        let mut bytes = vec![tag, 0x58];
+        bytes.extend_from_slice(&[0x66; 31]);
+        PublicKey::from_bytes(&bytes).unwrap()
+    }
+
+    fn parse(query: &str) -> Result<PaymentRequest> {
+        format!("helium:{}?{}", address(Network::MainNet), query).parse()
+    }
+
+    #[test]
+    fn round_trip() {
+        let request = PaymentRequest {
+            address: address(Network::MainNet),
+            amount: Some(Token::from_str("1.5").unwrap()),
+            token: TokenInput::Iot,
+            memo: Some(Memo(42)),
+        };
+        let parsed: PaymentRequest = request.to_string().parse().unwrap();
+        assert_eq!(parsed.address, request.address);
+        assert_eq!(
+            parsed.amount.map(|amount| amount.to_string()),
+            request.amount.map(|amount| amount.to_string())
+        );
+        assert_eq!(parsed.token, TokenInput::Iot);
+        assert_eq!(parsed.memo, Some(Memo(42)));
+    }
+
+    #[test]
+    fn bare_address() {
+        let address = address(Network::MainNet);
+        let parsed: PaymentRequest = format!("helium:{}", address).parse().unwrap();
+        assert_eq!(parsed.address, address);
+        assert!(parsed.amount.is_none());
+        assert_eq!(parsed.token, TokenInput::Hnt);
+        assert!(parsed.memo.is_none());
+    }
+
+    #[test]
+    fn scheme_is_case_insensitive() {
+        let parsed: PaymentRequest = format!("HELIUM:{}?amount=1", address(Network::MainNet))
+            .parse()
+            .unwrap();
+        assert!(parsed.amount.is_some());
+        assert!(format!("bitcoin:{}", address(Network::MainNet))
+            .parse::<PaymentRequest>()
+            .is_err());
+    }
+
+    #[test]
+    fn memo_is_percent_encoded() {
+        // all ones encode to base64 with both / and =
+        let memo = Memo(u64::MAX);
+        assert_eq!(memo.to_string(), "//////////8=");
+        let request = PaymentRequest {
+            address: address(Network::MainNet),
+            amount: None,
+            token: TokenInput::Hnt,
+            memo: Some(Memo(u64::MAX)),
+        };
+        let uri = request.to_string();
+        assert!(uri.ends_with("?memo=%2F%2F%2F%2F%2F%2F%2F%2F%2F%2F8%3D"));
+        let parsed: PaymentRequest = uri.parse().unwrap();
+        assert_eq!(parsed.memo, Some(memo));
+    }
+
+    #[test]
+    fn repeated_params_are_refused() {
+        assert!(parse("amount=1&amount=2").is_err());
+        assert!(parse("token=hnt&token=iot").is_err());
+    }
+
+    #[test]
+    fn unknown_params_are_refused() {
+        assert!(parse("amount=1&label=coffee").is_err());
+        assert!(parse("amount").is_err());
+    }
+
+    #[test]
+    fn zero_amount_is_refused() {
+        assert!(parse("amount=0").is_err());
+        assert!(parse("amount=-1").is_err());
+        assert!(parse("amount=0.1").is_ok());
+    }
+
+    #[test]
+    fn wrong_network_is_refused() {
+        let request: PaymentRequest = format!("helium:{}?amount=1", address(Network::TestNet))
+            .parse()
+            .unwrap();
+        assert!(request.check_network(Network::TestNet).is_ok());
+        assert!(request.check_network(Network::MainNet).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use super::{
+    addressbook::Address,
+    cmd::{pay::TokenInput, preflight::Preflight},
+    create_data_dir, data_dir,
+    journal::{self, Entry, Status},
+    Error, Network, Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use helium_api::models::Token;
+use helium_wallet::keypair::PublicKey;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+};
+
+const POLICY_FILE: &str = "policy.json";
+const OVERRIDE_LOG_FILE: &str = "policy-overrides.log";
+
+/// The kind of transaction a spend comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendKind {
+    Payment,
+    Burn,
+    Stake,
+    StakeTransfer,
+}
+
+/// Tokens leaving an account as part of a transaction
+#[derive(Debug, Clone)]
+pub struct Spend {
+    pub kind: SpendKind,
+    pub payer: PublicKey,
+    pub payee: PublicKey,
+    pub token: TokenInput,
+    pub amount: u64,
+    pub memo: Option<u64>,
+}
+
+/// The policy file as stored on disk. Amounts are given as decimal token
+/// strings keyed by token name and addresses may be @labels. Payee lists
+/// apply to payments, stakes and stake transfer payments. For example:
+///
+/// {
+///     "max_per_txn": { "hnt": "100" },
+///     "daily_cap": { "hnt": "250", "iot": "10000" },
+///     "deny_payees": ["@old-exchange"],
+///     "require_memo": ["@exchange"],
+///     "deny_third_party_burns": true
+/// }
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PolicyFile {
+    max_per_txn: BTreeMap<String, String>,
+    daily_cap: BTreeMap<String, String>,
+    allow_payees: Option<Vec<Address>>,
+    deny_payees: Vec<Address>,
+    require_memo: Vec<Address>,
+    deny_third_party_burns: bool,
+}
+
+/// Spending rules checked before any transaction is sent to the Ledger
+#[derive(Debug)]
+pub struct Policy {
+    max_per_txn: HashMap<TokenInput, u64>,
+    daily_cap: HashMap<TokenInput, u64>,
+    allow_payees: Option<Vec<PublicKey>>,
+    deny_payees: Vec<PublicKey>,
+    require_memo: Vec<PublicKey>,
+    deny_third_party_burns: bool,
+}
+
+impl Policy {
+    pub fn path() -> Result<PathBuf> {
+        Ok(data_dir()?.join(POLICY_FILE))
+    }
+
+    /// Loads the policy for the given network, if a policy file exists
+    pub fn load(network: Network) -> Result<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file: PolicyFile = serde_json::from_reader(fs::File::open(path)?)?;
+        let resolve = |addresses: Vec<Address>| -> Result<Vec<PublicKey>> {
+            addresses
+                .iter()
+                .map(|address| address.resolve(network))
+                .collect()
+        };
+        Ok(Some(Self {
+            max_per_txn: parse_limits(file.max_per_txn)?,
+            daily_cap: parse_limits(file.daily_cap)?,
+            allow_payees: file.allow_payees.map(&resolve).transpose()?,
+            deny_payees: resolve(file.deny_payees)?,
+            require_memo: resolve(file.require_memo)?,
+            deny_third_party_burns: file.deny_third_party_burns,
+        }))
+    }
+
+    /// Returns a description of every rule the given spends by `account`
+    /// break. Spends by other accounts are ignored.
+    pub fn violations(&self, account: &PublicKey, spends: &[Spend]) -> Result<Vec<String>> {
+        // the journal is only needed for the daily cap
+        let journal = if self.daily_cap.is_empty() {
+            vec![]
+        } else {
+            journal::entries()?
+        };
+        self.check(account, spends, &journal)
+    }
+
+    /// Checks the given spends against the rules, with the daily cap counted
+    /// from the given journal entries
+    fn check(
+        &self,
+        account: &PublicKey,
+        spends: &[Spend],
+        journal: &[Entry],
+    ) -> Result<Vec<String>> {
+        let spends: Vec<&Spend> = spends.iter().filter(|s| &s.payer == account).collect();
+        let mut violations = Vec::new();
+        let mut batch_totals: HashMap<TokenInput, u64> = HashMap::new();
+
+        for spend in &spends {
+            if let Some(max) = self.max_per_txn.get(&spend.token) {
+                if spend.amount > *max {
+                    violations.push(format!(
+                        "{} {} exceeds the per transaction limit of {}",
+                        Token::from(spend.amount),
+                        spend.token,
+                        Token::from(*max)
+                    ));
+                }
+            }
+            *batch_totals.entry(spend.token).or_default() += spend.amount;
+
+            if spend.kind == SpendKind::Burn {
+                if self.deny_third_party_burns && spend.payee != spend.payer {
+                    violations.push(format!("burns to third party {} are denied", spend.payee));
+                }
+            } else {
+                // payees include validators staked to, so the lists also
+                // restrict which validators may be staked
+                if let Some(allowed) = &self.allow_payees {
+                    if !allowed.contains(&spend.payee) {
+                        violations.push(format!("payee {} is not allowed", spend.payee));
+                    }
+                }
+                if self.deny_payees.contains(&spend.payee) {
+                    violations.push(format!("payee {} is denied", spend.payee));
+                }
+            }
+            if self.require_memo.contains(&spend.payee) && spend.memo.unwrap_or(0) == 0 {
+                violations.push(format!("payee {} requires a memo", spend.payee));
+            }
+        }
+
+        if !self.daily_cap.is_empty() {
+            let spent = spent_since(journal, account, Utc::now() - Duration::days(1))?;
+            for (token, total) in batch_totals {
+                if let Some(cap) = self.daily_cap.get(&token) {
+                    let already = spent.get(&token).copied().unwrap_or(0);
+                    if already + total > *cap {
+                        violations.push(format!(
+                            "{} {} would exceed the daily cap of {} ({} already signed in the last 24 hours)",
+                            Token::from(total),
+                            token,
+                            Token::from(*cap),
+                            Token::from(already)
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+fn parse_limits(limits: BTreeMap<String, String>) -> Result<HashMap<TokenInput, u64>> {
+    limits
+        .iter()
+        .map(|(token, amount)| -> Result<(TokenInput, u64)> {
+            let amount = Token::from_str(amount).map_err(|_| {
+                Error::Policy(format!("invalid amount \"{}\" for {}", amount, token))
+            })?;
+            Ok((TokenInput::from_str(token)?, u64::from(amount)))
+        })
+        .collect()
+}
+
+/// Totals per token spent by `account` in the given journal entries since the
+/// given time. Transactions journaled more than once are only counted once,
+/// by their latest entry, and not at all if that entry is a failure.
+fn spent_since(
+    journal: &[Entry],
+    account: &PublicKey,
+    since: DateTime<Utc>,
+) -> Result<HashMap<TokenInput, u64>> {
+    let mut latest = HashMap::new();
+    for entry in journal {
+        latest.insert(&entry.hash, entry);
+    }
+    let mut totals = HashMap::new();
+    for entry in latest.values() {
+        if entry.timestamp < since || entry.status == Status::Failed {
+            continue;
+        }
+        let txn = journal::from_envelope(&entry.envelope()?)?;
+        for spend in txn.spends()? {
+            if &spend.payer == account {
+                *totals.entry(spend.token).or_default() += spend.amount;
+            }
+        }
+    }
+    Ok(totals)
+}
+
+/// Appends an overridden set of violations to the override log
+pub fn log_override(account: &PublicKey, violations: &[String]) -> Result {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(create_data_dir()?.join(OVERRIDE_LOG_FILE))?;
+    for violation in violations {
+        writeln!(
+            file,
+            "{} {} {}",
+            Utc::now().to_rfc3339(),
+            account,
+            violation
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helium_proto::{BlockchainTxnPaymentV2, Payment};
+
+    /// The Ed25519 base point, which is a valid public key
+    const ED25519_BASE_POINT: [u8; 32] = [
+        0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+        0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+        0x66, 0x66,
+    ];
+
+    const HNT: u64 = 100_000_000;
+
+    /// The account spending in these tests
+    fn account() -> PublicKey {
+        key(0x01)
+    }
+
+    /// Another party. The policy never looks at networks, so the same point
+    /// with a testnet tag will do.
+    fn other() -> PublicKey {
+        key(0x11)
+    }
+
+    fn key(tag: u8) -> PublicKey {
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&ED25519_BASE_POINT);
+        PublicKey::from_bytes(&bytes).unwrap()
+    }
+
+    fn no_rules() -> Policy {
+        Policy {
+            max_per_txn: HashMap::new(),
+            daily_cap: HashMap::new(),
+            allow_payees: None,
+            deny_payees: vec![],
+            require_memo: vec![],
+            deny_third_party_burns: false,
+        }
+    }
+
+    fn spend(kind: SpendKind, payee: PublicKey, amount: u64) -> Spend {
+        Spend {
+            kind,
+            payer: account(),
+            payee,
+            token: TokenInput::Hnt,
+            amount,
+            memo: None,
+        }
+    }
+
+    fn entry(nonce: u64, amount: u64, status: Status) -> Entry {
+        let txn = BlockchainTxnPaymentV2 {
+            payer: account().to_vec(),
+            payments: vec![Payment {
+                payee: other().to_vec(),
+                amount,
+                ..Default::default()
+            }],
+            fee: 35_000,
+            nonce,
+            signature: vec![],
+        };
+        Entry::new(Some(0), None, Network::MainNet, &txn, status, None).unwrap()
+    }
+
+    #[test]
+    fn per_transaction_limit() {
+        let mut policy = no_rules();
+        policy.max_per_txn.insert(TokenInput::Hnt, 100 * HNT);
+        let at_limit = spend(SpendKind::Payment, other(), 100 * HNT);
+        let over_limit = spend(SpendKind::Burn, account(), 100 * HNT + 1);
+        assert!(policy
+            .check(&account(), &[at_limit], &[])
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            policy.check(&account(), &[over_limit], &[]).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn daily_cap_counts_the_latest_entry_of_each_transaction() {
+        let mut policy = no_rules();
+        policy.daily_cap.insert(TokenInput::Hnt, 250 * HNT);
+
+        let mut expired = entry(3, 100 * HNT, Status::Submitted);
+        expired.timestamp = Utc::now() - Duration::days(2);
+        let signed = entry(1, 100 * HNT, Status::Signed);
+        let failed = entry(2, 100 * HNT, Status::Submitted);
+        let journal = [
+            // signed, then submitted, so counted once
+            signed.clone(),
+            signed.with_status(Status::Submitted, None),
+            // submitted, then failed, so not counted
+            failed.clone(),
+            failed.with_status(Status::Failed, None),
+            // older than a day
+            expired,
+        ];
+
+        let up_to_cap = spend(SpendKind::Payment, other(), 150 * HNT);
+        let over_cap = spend(SpendKind::Payment, other(), 150 * HNT + 1);
+        assert!(policy
+            .check(&account(), &[up_to_cap], &journal)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            policy
+                .check(&account(), &[over_cap], &journal)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn payee_lists_apply_to_payments_and_stakes() {
+        let mut policy = no_rules();
+        policy.allow_payees = Some(vec![account()]);
+        let payment = spend(SpendKind::Payment, other(), HNT);
+        let stake = spend(SpendKind::Stake, other(), HNT);
+        assert_eq!(policy.check(&account(), &[payment], &[]).unwrap().len(), 1);
+        assert_eq!(policy.check(&account(), &[stake], &[]).unwrap().len(), 1);
+
+        let mut policy = no_rules();
+        policy.deny_payees = vec![other()];
+        let payment = spend(SpendKind::Payment, other(), HNT);
+        let allowed = spend(SpendKind::Payment, account(), HNT);
+        assert_eq!(policy.check(&account(), &[payment], &[]).unwrap().len(), 1);
+        assert!(policy
+            .check(&account(), &[allowed], &[])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn third_party_burns() {
+        let mut policy = no_rules();
+        let own = spend(SpendKind::Burn, account(), HNT);
+        let third_party = spend(SpendKind::Burn, other(), HNT);
+        assert!(policy
+            .check(&account(), &[third_party.clone()], &[])
+            .unwrap()
+            .is_empty());
+
+        policy.deny_third_party_burns = true;
+        // payee lists do not apply to burns
+        policy.deny_payees = vec![other()];
+        assert!(policy.check(&account(), &[own], &[]).unwrap().is_empty());
+        assert_eq!(
+            policy.check(&account(), &[third_party], &[]).unwrap().len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use super::{api, fmt, Error, Network, Opts, Result};
+use helium_api::models::Hnt;
+use rust_decimal::{prelude::*, Decimal};
+use serde_json::Value;
+
+/// Oracle prices are reported in units of 10^-8 USD
+const ORACLE_PRICE_SCALE: u32 = 8;
+/// Data Credits have a fixed price of $0.00001
+const DC_PER_USD: u64 = 100_000;
+const BONES_PER_HNT: u64 = 100_000_000;
+
+/// A price of HNT in USD, either reported by the chain's price oracles or
+/// supplied by the user
+#[derive(Debug, Clone, Copy)]
+pub struct Price {
+    pub usd: Decimal,
+    /// Block at which the oracle price was set, if it came from the oracles
+    pub block: Option<u64>,
+}
+
+impl Price {
+    /// Fetches the current oracle price of the given network
+    pub async fn current(network: Network) -> Result<Self> {
+        let price: Value = api::get(network, "/oracle/prices/current", &[])
+            .await?
+            .ok_or_else(|| Error::Price("no current oracle price".to_string()))?;
+        let usd = price
+            .get("price")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| Error::Price("invalid oracle price".to_string()))?;
+        Ok(Self {
+            usd: Decimal::new(usd as i64, ORACLE_PRICE_SCALE),
+            block: price.get("block").and_then(Value::as_u64),
+        })
+    }
+
+    /// The price to value amounts in for the given options: the user
+    /// supplied price if given, the oracle price if --usd is set and None
+    /// otherwise
+    pub async fn for_opts(opts: &Opts, network: Network) -> Result<Option<Self>> {
+        match opts.price {
+            Some(usd) => Ok(Some(Self { usd, block: None })),
+            None if opts.usd => Ok(Some(Self::current(network).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn usd_value(&self, hnt: Decimal) -> Decimal {
+        hnt * self.usd
+    }
+
+    /// Formats the USD value of the given amount of HNT
+    pub fn fmt_usd(&self, hnt: Decimal) -> String {
+        format!("${:.2}", self.usd_value(hnt).round_dp(2))
+    }
+
+    /// The amount of DC minted by burning the given amount of HNT
+    pub fn dc_for_hnt(&self, hnt: Decimal) -> u64 {
+        (self.usd_value(hnt) * Decimal::from(DC_PER_USD))
+            .floor()
+            .to_u64()
+            .unwrap_or(0)
+    }
+
+    /// The smallest amount of HNT that mints at least the given amount of DC
+    pub fn hnt_for_dc(&self, dc: u64) -> Result<Hnt> {
+        if self.usd <= Decimal::ZERO {
+            return Err(Error::Price(format!("invalid HNT price {}", self.usd)));
+        }
+        let bones = (Decimal::from(dc) * Decimal::from(BONES_PER_HNT)
+            / Decimal::from(DC_PER_USD)
+            / self.usd)
+            .ceil()
+            .to_u64()
+            .ok_or_else(|| Error::Price(format!("{} DC is out of range", dc)))?;
+        Ok(Hnt::from(bones))
+    }
+
+    /// Number of blocks since the oracle price was set. Errors for user
+    /// supplied prices, whose age is unknown.
+    pub async fn age(&self, network: Network) -> Result<u64> {
+        let block = self
+            .block
+            .ok_or_else(|| Error::Price("age of a user supplied price is unknown".to_string()))?;
+        Ok(api::height(network).await?.saturating_sub(block))
+    }
+
+    /// Errors if the oracle price was set more than `max_age` blocks ago
+    pub async fn check_age(&self, network: Network, max_age: u64) -> Result {
+        let age = self.age(network).await?;
+        if age > max_age {
+            return Err(Error::Price(format!(
+                "oracle price is {} blocks old, more than the allowed {}",
+                age, max_age
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.block {
+            Some(block) => write!(
+                f,
+                "{} USD per HNT (oracle price at block {})",
+                self.usd, block
+            ),
+            None => write!(f, "{} USD per HNT (user supplied)", self.usd),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use super::{api, Error, Network, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fs, path::Path, sync::Mutex};
+
+pub type Vars = Map<String, Value>;
+
+/// Seconds fetched chain vars are reused for before they are fetched again
+const TTL_SECS: i64 = 300;
+
+/// Chain vars of this session, one snapshot per network
+static SESSION: Mutex<Vec<Cached>> = Mutex::new(Vec::new());
+
+struct Cached {
+    snapshot: Snapshot,
+    /// Loaded with --vars, so never refetched
+    pinned: bool,
+}
+
+/// The chain vars of a network at a point in time, as exported for offline
+/// use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub network: String,
+    pub fetched_at: DateTime<Utc>,
+    /// Chain height when the vars were fetched
+    pub height: Option<u64>,
+    pub vars: Vars,
+}
+
+impl Snapshot {
+    /// Fetches the current chain vars of the given network
+    pub async fn fetch(network: Network) -> Result<Self> {
+        let vars = api::get(network, "/vars", &[])
+            .await?
+            .ok_or_else(|| Error::Api("missing chain vars".to_string()))?;
+        Ok(Self {
+            network: network.to_string(),
+            fetched_at: Utc::now(),
+            height: None,
+            vars,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| Error::Vars(format!("invalid snapshot {}: {}", path.display(), err)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn age(&self) -> Duration {
+        Utc::now() - self.fetched_at
+    }
+}
+
+/// The chain vars of the given network. Vars are fetched once and reused for
+/// a while, or taken from the snapshot loaded with `pin` without any request.
+pub async fn get(network: Network) -> Result<Vars> {
+    {
+        let session = SESSION.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(cached) = session
+            .iter()
+            .find(|cached| cached.snapshot.network == network.to_string())
+        {
+            if cached.pinned || cached.snapshot.age() < Duration::seconds(TTL_SECS) {
+                return Ok(cached.snapshot.vars.clone());
+            }
+        } else if let Some(cached) = session.iter().find(|cached| cached.pinned) {
+            return Err(Error::Vars(format!(
+                "the loaded snapshot is for {} but {} vars are needed",
+                cached.snapshot.network, network
+            )));
+        }
+    }
+
+    let snapshot = Snapshot::fetch(network).await?;
+    let vars = snapshot.vars.clone();
+    let mut session = SESSION.lock().unwrap_or_else(|err| err.into_inner());
+    session.retain(|cached| cached.snapshot.network != snapshot.network);
+    session.push(Cached {
+        snapshot,
+        pinned: false,
+    });
+    Ok(vars)
+}
+
+/// Uses the given snapshot for the rest of the session instead of fetching
+/// chain vars, warning if it is older than `max_age`
+pub fn pin(snapshot: Snapshot, max_age: Duration) {
+    if snapshot.age() > max_age {
+        println!(
+            "WARNING: the {} chain vars snapshot was taken {} hours ago at {}. Fees and \
+            checks may not match the network anymore",
+            snapshot.network,
+            snapshot.age().num_hours(),
+            snapshot.fetched_at.to_rfc3339()
+        );
+    }
+    let mut session = SESSION.lock().unwrap_or_else(|err| err.into_inner());
+    session.retain(|cached| cached.snapshot.network != snapshot.network);
+    session.push(Cached {
+        snapshot,
+        pinned: true,
+    });
+}
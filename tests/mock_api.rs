@@ -0,0 +1,151 @@
+//! Drives commands against the mock Helium API. The API URL and data
+//! directory are taken from the environment, so tests run one at a time
+//! through `serial` to keep them from changing it under each other.
+#![cfg(feature = "mock-api")]
+
+use helium_ledger::{
+    cmd::{fee, submit, PublicKey, TxnEnvelope},
+    mock::{Failure, Fixtures, MockApi},
+    submit_txn_with_retry, Network, Opts, StructOpt,
+};
+use helium_proto::{BlockchainTxnPaymentV2, Payment};
+use helium_wallet::traits::B64;
+use std::{env, future::Future, net::SocketAddr, sync::Mutex};
+
+/// The Ed25519 base point, which is a valid public key
+const ED25519_BASE_POINT: [u8; 32] = [
+    0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+];
+
+/// Held by the running test, since tests share the process environment
+static ENV: Mutex<()> = Mutex::new(());
+
+/// Runs a test on a runtime of its own once no other test is running
+fn serial<F: Future<Output = ()>>(test: F) {
+    let _guard = ENV.lock().unwrap_or_else(|err| err.into_inner());
+    tokio::runtime::Runtime::new()
+        .expect("runtime")
+        .block_on(test)
+}
+
+fn mock() -> MockApi {
+    MockApi::start(Fixtures::default(), SocketAddr::from(([127, 0, 0, 1], 0))).expect("mock API")
+}
+
+/// Starts a mock API and points the given network at it
+fn start(network: Network) -> MockApi {
+    let mock = mock();
+    let var = match network {
+        Network::MainNet => "HELIUM_API_URL",
+        Network::TestNet => "HELIUM_TESTNET_API_URL",
+    };
+    env::set_var(var, mock.url());
+    env::set_var(
+        "HELIUM_LEDGER_DIR",
+        env::temp_dir().join(format!("helium-ledger-test-{}", std::process::id())),
+    );
+    mock
+}
+
+fn key(network: Network) -> PublicKey {
+    let tag = match network {
+        Network::MainNet => 0x01,
+        Network::TestNet => 0x11,
+    };
+    let mut bytes = vec![tag];
+    bytes.extend_from_slice(&ED25519_BASE_POINT);
+    PublicKey::from_bytes(&bytes).expect("public key")
+}
+
+fn payment(network: Network, signature: Vec<u8>) -> BlockchainTxnPaymentV2 {
+    let key = key(network);
+    BlockchainTxnPaymentV2 {
+        payer: key.to_vec(),
+        payments: vec![Payment {
+            payee: key.to_vec(),
+            amount: 100_000_000,
+            ..Default::default()
+        }],
+        fee: 35_000,
+        nonce: 1,
+        signature,
+    }
+}
+
+fn opts() -> Opts {
+    Opts::from_iter(&["helium-ledger-cli"])
+}
+
+#[test]
+fn submit_retries_injected_failures() {
+    serial(async {
+        let mock = start(Network::TestNet);
+        mock.fail(Failure {
+            path: "/pending_transactions".to_string(),
+            status: 503,
+            count: 1,
+            delay_ms: 0,
+        })
+        .expect("inject failure");
+
+        let envelope = payment(Network::TestNet, vec![1; 64]).in_envelope();
+        let status = submit_txn_with_retry(Network::TestNet, &envelope)
+            .await
+            .expect("submitted after a retry");
+
+        let submissions = mock.submissions();
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].hash, status.hash);
+        assert_eq!(submissions[0].txn_type, "payment_v2");
+    })
+}
+
+#[test]
+fn mock_refuses_zero_failure_count() {
+    serial(async {
+        let mock = mock();
+        let failure = Failure {
+            path: "/accounts".to_string(),
+            status: 500,
+            count: 0,
+            delay_ms: 0,
+        };
+        assert!(mock.fail(failure).is_err());
+    })
+}
+
+#[test]
+fn submit_and_quote_payments() {
+    serial(async {
+        let mock = start(Network::MainNet);
+
+        // quoting a payment builds it against the mock without a Ledger
+        let address = key(Network::MainNet).to_string();
+        let quote = fee::Cmd::from_iter(&["fee", "--address", &address, "pay", &address, "1"]);
+        quote.run(opts()).await.expect("fee quoted");
+        assert!(mock.submissions().is_empty());
+
+        // a payment without a payer signature is refused before it is sent
+        let unsigned = payment(Network::MainNet, vec![])
+            .in_envelope()
+            .to_b64()
+            .expect("base64");
+        let cmd = submit::Cmd::from_iter(&["submit", &unsigned]);
+        assert!(cmd.run(opts()).await.is_err());
+        assert!(mock.submissions().is_empty());
+
+        let signed = payment(Network::MainNet, vec![1; 64])
+            .in_envelope()
+            .to_b64()
+            .expect("base64");
+        let cmd = submit::Cmd::from_iter(&["submit", &signed]);
+        let (hash, network) = cmd.run(opts()).await.expect("submitted").expect("hash");
+        assert_eq!(network, Network::MainNet);
+
+        let submissions = mock.submissions();
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].hash, hash);
+        assert_eq!(submissions[0].txn, signed);
+    })
+}